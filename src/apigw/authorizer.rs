@@ -0,0 +1,363 @@
+use super::{
+    ApiGatewayProxyRequestContext, ApiGatewayRequestIdentity, ApiGatewayV2httpRequestContext,
+    ApiGatewayV2httpRequestContextAuthorizerIamDescription, ApiGatewayWebsocketProxyRequestContext,
+};
+use crate::custom_serde::Lenient;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// `AuthorizerContext` is the authorizer output of a request, in the same shape for
+/// REST, HTTP and WebSocket APIs.
+///
+/// REST and WebSocket APIs send every value of a Lambda authorizer context, and every
+/// Cognito user pool claim, as a string. `deserialize_claims` and `deserialize_lambda`
+/// parse those strings when the target type expects a number or a boolean.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuthorizerContext {
+    /// The `principalId` returned by a Lambda authorizer.
+    pub principal_id: Option<String>,
+    /// The claims of a JWT or Cognito user pool authorizer.
+    pub claims: Map<String, Value>,
+    /// The scopes of a JWT or Cognito user pool authorizer.
+    pub scopes: Vec<String>,
+    /// The caller of a request authorized with IAM.
+    pub iam: Option<IamIdentity>,
+    /// The context returned by a Lambda authorizer.
+    pub lambda: Map<String, Value>,
+}
+
+/// `IamIdentity` is the caller of a request authorized with IAM.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IamIdentity {
+    pub access_key: Option<String>,
+    pub account_id: Option<String>,
+    pub caller_id: Option<String>,
+    pub principal_org_id: Option<String>,
+    pub user_arn: Option<String>,
+    pub user_id: Option<String>,
+    pub cognito_identity: Option<CognitoIdentity>,
+}
+
+/// `CognitoIdentity` is the Cognito identity pool identity of an IAM caller.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CognitoIdentity {
+    pub identity_id: Option<String>,
+    pub identity_pool_id: Option<String>,
+    pub amr: Vec<String>,
+    pub authentication_type: Option<String>,
+    pub authentication_provider: Option<String>,
+}
+
+impl AuthorizerContext {
+    /// Returns a claim as a string, if it is one.
+    pub fn claim(&self, name: &str) -> Option<&str> {
+        self.claims.get(name).and_then(Value::as_str)
+    }
+
+    /// Returns whether the token was granted `scope`.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Deserializes the JWT or Cognito user pool claims into `T`.
+    pub fn deserialize_claims<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        T::deserialize(Lenient(Value::Object(self.claims.clone())))
+    }
+
+    /// Deserializes the Lambda authorizer context into `T`.
+    pub fn deserialize_lambda<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        T::deserialize(Lenient(Value::Object(self.lambda.clone())))
+    }
+
+    /// Builds the context from the `authorizer` map of REST and WebSocket APIs,
+    /// where Cognito user pools add `claims` and `scopes` next to the Lambda authorizer values.
+    fn from_authorizer_map(
+        mut lambda: Map<String, Value>,
+        identity: &ApiGatewayRequestIdentity,
+    ) -> Self {
+        let claims = match lambda.remove("claims") {
+            Some(Value::Object(claims)) => claims,
+            _ => Map::new(),
+        };
+        let scopes = match lambda.remove("scopes") {
+            Some(Value::Array(scopes)) => scopes
+                .into_iter()
+                .filter_map(|s| s.as_str().map(String::from))
+                .collect(),
+            Some(Value::String(scopes)) => scopes.split_whitespace().map(String::from).collect(),
+            _ => Vec::new(),
+        };
+        AuthorizerContext {
+            principal_id: lambda
+                .get("principalId")
+                .and_then(Value::as_str)
+                .map(String::from),
+            claims,
+            scopes,
+            iam: IamIdentity::from_request_identity(identity),
+            lambda,
+        }
+    }
+}
+
+impl IamIdentity {
+    fn from_request_identity(identity: &ApiGatewayRequestIdentity) -> Option<Self> {
+        if identity.user_arn.is_none() && identity.access_key.is_none() {
+            return None;
+        }
+        let cognito_identity = identity
+            .cognito_identity_id
+            .as_ref()
+            .map(|_| CognitoIdentity {
+                identity_id: identity.cognito_identity_id.clone(),
+                identity_pool_id: identity.cognito_identity_pool_id.clone(),
+                amr: Vec::new(),
+                authentication_type: identity.cognito_authentication_type.clone(),
+                authentication_provider: identity.cognito_authentication_provider.clone(),
+            });
+        Some(IamIdentity {
+            access_key: identity.access_key.clone(),
+            account_id: identity.account_id.clone(),
+            caller_id: identity.caller.clone(),
+            principal_org_id: None,
+            user_arn: identity.user_arn.clone(),
+            user_id: identity.user.clone(),
+            cognito_identity,
+        })
+    }
+}
+
+impl<'a> From<&'a ApiGatewayV2httpRequestContextAuthorizerIamDescription> for IamIdentity {
+    fn from(iam: &'a ApiGatewayV2httpRequestContextAuthorizerIamDescription) -> Self {
+        IamIdentity {
+            access_key: iam.access_key.clone(),
+            account_id: iam.account_id.clone(),
+            caller_id: iam.caller_id.clone(),
+            principal_org_id: iam.principal_org_id.clone(),
+            user_arn: iam.user_arn.clone(),
+            user_id: iam.user_id.clone(),
+            cognito_identity: iam.cognito_identity.as_ref().map(|c| CognitoIdentity {
+                identity_id: c.identity_id.clone(),
+                identity_pool_id: c.identity_pool_id.clone(),
+                amr: c.amr.clone(),
+                authentication_type: None,
+                authentication_provider: None,
+            }),
+        }
+    }
+}
+
+fn to_map<T: Serialize>(values: &HashMap<String, T>) -> Map<String, Value> {
+    values
+        .iter()
+        .filter_map(|(k, v)| serde_json::to_value(v).ok().map(|v| (k.clone(), v)))
+        .collect()
+}
+
+impl<T1> ApiGatewayProxyRequestContext<T1>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+{
+    /// Returns the authorizer output and IAM caller of a REST API request.
+    pub fn authorizer_context(&self) -> AuthorizerContext {
+        AuthorizerContext::from_authorizer_map(to_map(&self.authorizer), &self.identity)
+    }
+}
+
+impl<T1, T2> ApiGatewayWebsocketProxyRequestContext<T1, T2>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+    T2: DeserializeOwned,
+    T2: Serialize,
+{
+    /// Returns the authorizer output and IAM caller of a WebSocket API request.
+    pub fn authorizer_context(&self) -> AuthorizerContext {
+        let authorizer = self
+            .authorizer
+            .as_ref()
+            .and_then(|a| serde_json::to_value(a).ok());
+        let authorizer = match authorizer {
+            Some(Value::Object(authorizer)) => authorizer,
+            _ => Map::new(),
+        };
+        AuthorizerContext::from_authorizer_map(authorizer, &self.identity)
+    }
+}
+
+impl<T1> ApiGatewayV2httpRequestContext<T1>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+{
+    /// Returns the authorizer output of an HTTP API request.
+    pub fn authorizer_context(&self) -> AuthorizerContext {
+        let authorizer = match self.authorizer {
+            Some(ref authorizer) => authorizer,
+            None => return AuthorizerContext::default(),
+        };
+        let (claims, scopes) = match authorizer.jwt {
+            Some(ref jwt) => (
+                jwt.claims
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                    .collect(),
+                jwt.scopes.clone().unwrap_or_default(),
+            ),
+            None => (Map::new(), Vec::new()),
+        };
+        let lambda = to_map(&authorizer.lambda);
+        AuthorizerContext {
+            principal_id: lambda
+                .get("principalId")
+                .and_then(Value::as_str)
+                .map(String::from),
+            claims,
+            scopes,
+            iam: authorizer.iam.as_ref().map(IamIdentity::from),
+            lambda,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::apigw::{
+        ApiGatewayProxyRequest, ApiGatewayV2httpRequest, ApiGatewayWebsocketProxyRequest,
+    };
+
+    extern crate serde_json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct Client {
+        principal_id: String,
+        client_id: u32,
+        client_name: String,
+    }
+
+    #[test]
+    fn example_apigw_request_authorizer_context() {
+        let data = include_bytes!("../fixtures/example-apigw-request.json");
+        let parsed: ApiGatewayProxyRequest = serde_json::from_slice(data).unwrap();
+        let context = parsed.request_context.authorizer_context();
+
+        assert_eq!(Some("admin"), context.principal_id.as_deref());
+        let client: Client = context.deserialize_lambda().unwrap();
+        assert_eq!(1, client.client_id);
+        assert_eq!("Exata", client.client_name);
+
+        let iam = context.iam.unwrap();
+        assert_eq!(Some("theUserArn"), iam.user_arn.as_deref());
+        assert_eq!(
+            Some("theCognitoIdentityId"),
+            iam.cognito_identity.unwrap().identity_id.as_deref()
+        );
+    }
+
+    #[test]
+    fn rest_cognito_user_pool_claims() {
+        #[derive(Deserialize)]
+        struct Claims {
+            sub: String,
+            email_verified: bool,
+            auth_time: u64,
+            #[serde(rename = "custom:age")]
+            age: Option<u8>,
+        }
+
+        let parsed: ApiGatewayProxyRequest = serde_json::from_value(json!({
+            "httpMethod": "GET",
+            "requestContext": {
+                "httpMethod": "GET",
+                "requestTimeEpoch": 0,
+                "authorizer": {
+                    "claims": {
+                        "sub": "123",
+                        "email_verified": "true",
+                        "auth_time": "1589522469",
+                        "custom:age": "42"
+                    },
+                    "scopes": ["email", "openid"]
+                }
+            }
+        }))
+        .unwrap();
+        let context = parsed.request_context.authorizer_context();
+
+        let claims: Claims = context.deserialize_claims().unwrap();
+        assert_eq!("123", claims.sub);
+        assert!(claims.email_verified);
+        assert_eq!(1589522469, claims.auth_time);
+        assert_eq!(Some(42), claims.age);
+        assert!(context.has_scope("openid"));
+        assert!(context.lambda.is_empty());
+        assert!(context.iam.is_none());
+    }
+
+    #[test]
+    fn example_apigw_v2_authorizer_contexts() {
+        let data = include_bytes!("../fixtures/example-apigw-v2-request-jwt-authorizer.json");
+        let parsed: ApiGatewayV2httpRequest = serde_json::from_slice(data).unwrap();
+        let context = parsed.request_context.authorizer_context();
+        assert_eq!(Some("value1"), context.claim("claim1"));
+        assert_eq!(vec!["scope1", "scope2"], context.scopes);
+
+        let data = include_bytes!("../fixtures/example-apigw-v2-request-lambda-authorizer.json");
+        let parsed: ApiGatewayV2httpRequest = serde_json::from_slice(data).unwrap();
+        let context = parsed.request_context.authorizer_context();
+        assert_eq!(Some(&json!("value")), context.lambda.get("key"));
+
+        let data = include_bytes!("../fixtures/example-apigw-v2-request-iam.json");
+        let parsed: ApiGatewayV2httpRequest = serde_json::from_slice(data).unwrap();
+        let iam = parsed.request_context.authorizer_context().iam.unwrap();
+        assert_eq!(Some("AwsOrgId"), iam.principal_org_id.as_deref());
+        assert_eq!(vec!["foo"], iam.cognito_identity.unwrap().amr);
+    }
+
+    #[test]
+    fn example_apigw_websocket_authorizer_context() {
+        let data = include_bytes!("../fixtures/example-apigw-websocket-request.json");
+        let parsed: ApiGatewayWebsocketProxyRequest = serde_json::from_slice(data).unwrap();
+        let context = parsed.request_context.authorizer_context();
+        let client: Client = context.deserialize_lambda().unwrap();
+        assert_eq!(
+            Client {
+                principal_id: "admin".to_string(),
+                client_id: 1,
+                client_name: "Exata".to_string(),
+            },
+            client
+        );
+    }
+
+    #[test]
+    fn lenient_values_keep_strings() {
+        #[derive(Deserialize)]
+        struct Values {
+            number_string: String,
+            not_a_number: Option<i32>,
+        }
+
+        let context = AuthorizerContext {
+            lambda: json!({"number_string": "42", "not_a_number": "abc"})
+                .as_object()
+                .cloned()
+                .unwrap(),
+            ..Default::default()
+        };
+        assert!(context.deserialize_lambda::<Values>().is_err());
+
+        let context = AuthorizerContext {
+            lambda: json!({"number_string": "42"}).as_object().cloned().unwrap(),
+            ..Default::default()
+        };
+        let values: Values = context.deserialize_lambda().unwrap();
+        assert_eq!("42", values.number_string);
+        assert_eq!(None, values.not_a_number);
+    }
+}
@@ -0,0 +1,233 @@
+use super::ApiGatewayWebsocketProxyRequest;
+use crate::encodings::Body;
+use chrono::{DateTime, Utc};
+use http::{Method, Request};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::convert::TryFrom;
+
+/// Characters that are kept verbatim in a connection id path segment.
+const CONNECTION_ID_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'=');
+
+fn connection_uri(endpoint: &str, connection_id: &str) -> String {
+    format!(
+        "{}/@connections/{}",
+        endpoint.trim_end_matches('/'),
+        utf8_percent_encode(connection_id, CONNECTION_ID_ENCODE_SET)
+    )
+}
+
+impl<T1, T2> ApiGatewayWebsocketProxyRequest<T1, T2>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+    T2: DeserializeOwned,
+    T2: Serialize,
+{
+    /// Returns the management API endpoint of the WebSocket API, `https://{domain_name}/{stage}`.
+    ///
+    /// APIs behind a custom domain name with a base path mapping must use their
+    /// `execute-api` endpoint instead.
+    pub fn connections_endpoint(&self) -> Option<String> {
+        let context = &self.request_context;
+        match (context.domain_name.as_deref(), context.stage.as_deref()) {
+            (Some(domain_name), Some(stage)) => Some(format!("https://{}/{}", domain_name, stage)),
+            _ => None,
+        }
+    }
+
+    /// Returns the callback URL of the connection that sent this request,
+    /// `https://{domain_name}/{stage}/@connections/{connection_id}`.
+    pub fn connection_url(&self) -> Option<String> {
+        let connection_id = self.request_context.connection_id.as_deref()?;
+        Some(connection_uri(&self.connections_endpoint()?, connection_id))
+    }
+
+    /// Builds a request that sends `data` to the connection that sent this request.
+    pub fn post_to_connection<B: Into<Body>>(&self, data: B) -> Option<PostToConnectionRequest> {
+        Some(PostToConnectionRequest {
+            endpoint: self.connections_endpoint()?,
+            connection_id: self.request_context.connection_id.clone()?,
+            data: data.into(),
+        })
+    }
+
+    /// Builds a request that describes the connection that sent this request.
+    pub fn get_connection(&self) -> Option<GetConnectionRequest> {
+        Some(GetConnectionRequest {
+            endpoint: self.connections_endpoint()?,
+            connection_id: self.request_context.connection_id.clone()?,
+        })
+    }
+
+    /// Builds a request that disconnects the connection that sent this request.
+    pub fn delete_connection(&self) -> Option<DeleteConnectionRequest> {
+        Some(DeleteConnectionRequest {
+            endpoint: self.connections_endpoint()?,
+            connection_id: self.request_context.connection_id.clone()?,
+        })
+    }
+}
+
+/// `PostToConnectionRequest` sends data to a connected WebSocket client.
+///
+/// `endpoint` is the management API endpoint, e.g. `https://{api-id}.execute-api.{region}.amazonaws.com/{stage}`.
+/// The converted `http::Request` is unsigned; sign it with SigV4 for the `execute-api` service before sending it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PostToConnectionRequest {
+    pub endpoint: String,
+    pub connection_id: String,
+    pub data: Body,
+}
+
+/// `GetConnectionRequest` asks for information about a WebSocket connection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GetConnectionRequest {
+    pub endpoint: String,
+    pub connection_id: String,
+}
+
+/// `DeleteConnectionRequest` disconnects a WebSocket client.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeleteConnectionRequest {
+    pub endpoint: String,
+    pub connection_id: String,
+}
+
+/// `GetConnectionResponse` is the body returned by the management API for a `GetConnectionRequest`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConnectionResponse {
+    pub connected_at: DateTime<Utc>,
+    pub identity: GetConnectionIdentity,
+    pub last_active_at: DateTime<Utc>,
+}
+
+/// `GetConnectionIdentity` identifies the client of a WebSocket connection.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConnectionIdentity {
+    #[serde(default)]
+    pub source_ip: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+impl TryFrom<PostToConnectionRequest> for Request<Body> {
+    type Error = http::Error;
+
+    fn try_from(req: PostToConnectionRequest) -> Result<Self, Self::Error> {
+        Request::builder()
+            .method(Method::POST)
+            .uri(connection_uri(&req.endpoint, &req.connection_id))
+            .body(req.data)
+    }
+}
+
+impl TryFrom<GetConnectionRequest> for Request<Body> {
+    type Error = http::Error;
+
+    fn try_from(req: GetConnectionRequest) -> Result<Self, Self::Error> {
+        Request::builder()
+            .method(Method::GET)
+            .uri(connection_uri(&req.endpoint, &req.connection_id))
+            .body(Body::Empty)
+    }
+}
+
+impl TryFrom<DeleteConnectionRequest> for Request<Body> {
+    type Error = http::Error;
+
+    fn try_from(req: DeleteConnectionRequest) -> Result<Self, Self::Error> {
+        Request::builder()
+            .method(Method::DELETE)
+            .uri(connection_uri(&req.endpoint, &req.connection_id))
+            .body(Body::Empty)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    extern crate serde_json;
+
+    fn request() -> ApiGatewayWebsocketProxyRequest {
+        let mut request = ApiGatewayWebsocketProxyRequest::default();
+        request.request_context.domain_name =
+            Some("abcdef123.execute-api.us-east-1.amazonaws.com".to_string());
+        request.request_context.stage = Some("prod".to_string());
+        request.request_context.connection_id = Some("TWegAcC4EowCHnA=".to_string());
+        request
+    }
+
+    #[test]
+    fn example_websocket_connection_url() {
+        let data = include_bytes!("../fixtures/example-apigw-websocket-request.json");
+        let parsed: ApiGatewayWebsocketProxyRequest = serde_json::from_slice(data).unwrap();
+        assert_eq!(
+            Some("https://*.execute-api.eu-central-1.amazonaws.com/testStage/@connections/TWegAcC4EowCHnA="),
+            parsed.connection_url().as_deref()
+        );
+
+        assert_eq!(
+            None,
+            ApiGatewayWebsocketProxyRequest::<serde_json::Value>::default().connection_url()
+        );
+    }
+
+    #[test]
+    fn post_to_connection_into_http_request() {
+        let post = request().post_to_connection("hello").unwrap();
+        let req = Request::try_from(post).unwrap();
+        assert_eq!(Method::POST, req.method());
+        assert_eq!(
+            "https://abcdef123.execute-api.us-east-1.amazonaws.com/prod/@connections/TWegAcC4EowCHnA=",
+            req.uri().to_string()
+        );
+        assert_eq!(&Body::from("hello"), req.body());
+    }
+
+    #[test]
+    fn connection_requests_against_custom_endpoint() {
+        let mut get = request().get_connection().unwrap();
+        get.endpoint = "http://localhost:3001/".to_string();
+        let req = Request::try_from(get).unwrap();
+        assert_eq!(Method::GET, req.method());
+        assert_eq!(
+            "http://localhost:3001/@connections/TWegAcC4EowCHnA=",
+            req.uri().to_string()
+        );
+
+        let delete = DeleteConnectionRequest {
+            endpoint: "http://localhost:3001".to_string(),
+            connection_id: "a/b".to_string(),
+        };
+        let req = Request::try_from(delete).unwrap();
+        assert_eq!(Method::DELETE, req.method());
+        assert_eq!("/@connections/a%2Fb", req.uri().path());
+        assert_eq!(&Body::Empty, req.body());
+    }
+
+    #[test]
+    fn deserialize_get_connection_response() {
+        let response: GetConnectionResponse = serde_json::from_value(json!({
+            "connectedAt": "2023-01-02T03:04:05Z",
+            "identity": {"sourceIp": "192.0.2.1", "userAgent": "test"},
+            "lastActiveAt": "2023-01-02T03:05:00Z"
+        }))
+        .unwrap();
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap(),
+            response.connected_at
+        );
+        assert_eq!(Some("192.0.2.1"), response.identity.source_ip.as_deref());
+    }
+}
@@ -0,0 +1,288 @@
+use super::{
+    ApiGatewayCustomAuthorizerRequest, ApiGatewayCustomAuthorizerRequestTypeRequest,
+    ApiGatewayV2CustomAuthorizerV1Request, ApiGatewayV2CustomAuthorizerV2Request,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+use std::{error, fmt};
+
+const WILDCARD: &str = "*";
+
+/// `InvalidMethodArn` is returned when a string is not an `execute-api` method ARN.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidMethodArn(String);
+
+impl fmt::Display for InvalidMethodArn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid execute-api method ARN: {}", self.0)
+    }
+}
+
+impl error::Error for InvalidMethodArn {}
+
+/// `MethodArn` is the ARN of an API Gateway method or route, as sent to custom authorizers:
+/// `arn:{partition}:execute-api:{region}:{account}:{api}/{stage}/{verb}/{path}`.
+///
+/// WebSocket routes have no path, and their verb is the route key, e.g. `$connect`.
+/// Any part after the account can be replaced by the `*` wildcard.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MethodArn {
+    partition: String,
+    region: String,
+    account_id: String,
+    api_id: String,
+    stage: String,
+    verb: String,
+    path: Option<String>,
+}
+
+impl MethodArn {
+    /// Returns the AWS partition, e.g. `aws`.
+    pub fn partition(&self) -> &str {
+        &self.partition
+    }
+
+    /// Returns the region of the API.
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    /// Returns the account that owns the API.
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    /// Returns the API id.
+    pub fn api_id(&self) -> &str {
+        &self.api_id
+    }
+
+    /// Returns the deployment stage.
+    pub fn stage(&self) -> &str {
+        &self.stage
+    }
+
+    /// Returns the HTTP method, or the route key of a WebSocket route.
+    pub fn verb(&self) -> &str {
+        &self.verb
+    }
+
+    /// Returns the resource path without its leading `/`, e.g. `pets/1`.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Replaces the stage.
+    pub fn with_stage(mut self, stage: &str) -> Self {
+        self.stage = stage.to_string();
+        self
+    }
+
+    /// Replaces the HTTP method or route key.
+    pub fn with_verb(mut self, verb: &str) -> Self {
+        self.verb = verb.to_string();
+        self
+    }
+
+    /// Replaces the resource path. A leading `/` is ignored.
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.trim_start_matches('/').to_string());
+        self
+    }
+
+    /// Matches every stage of the API.
+    pub fn any_stage(self) -> Self {
+        self.with_stage(WILDCARD)
+    }
+
+    /// Matches every HTTP method or route key.
+    pub fn any_verb(self) -> Self {
+        self.with_verb(WILDCARD)
+    }
+
+    /// Matches every resource path.
+    pub fn any_path(self) -> Self {
+        self.with_path(WILDCARD)
+    }
+}
+
+impl FromStr for MethodArn {
+    type Err = InvalidMethodArn;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidMethodArn(s.to_string());
+        let parts: Vec<&str> = s.splitn(6, ':').collect();
+        let (partition, region, account_id, resource) = match parts.as_slice() {
+            ["arn", partition, "execute-api", region, account_id, resource]
+                if !partition.is_empty() =>
+            {
+                (partition, region, account_id, resource)
+            }
+            _ => return Err(invalid()),
+        };
+        let resource: Vec<&str> = resource.splitn(4, '/').collect();
+        let (api_id, stage, verb, path) = match resource.as_slice() {
+            [api_id, stage, verb] => (api_id, stage, verb, None),
+            [api_id, stage, verb, path] => (api_id, stage, verb, Some(path.to_string())),
+            _ => return Err(invalid()),
+        };
+        if api_id.is_empty() || stage.is_empty() || verb.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(MethodArn {
+            partition: partition.to_string(),
+            region: region.to_string(),
+            account_id: account_id.to_string(),
+            api_id: api_id.to_string(),
+            stage: stage.to_string(),
+            verb: verb.to_string(),
+            path,
+        })
+    }
+}
+
+impl fmt::Display for MethodArn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "arn:{}:execute-api:{}:{}:{}/{}/{}",
+            self.partition, self.region, self.account_id, self.api_id, self.stage, self.verb
+        )?;
+        match self.path {
+            Some(ref path) => write!(f, "/{}", path),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Serialize for MethodArn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MethodArn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+fn parse(arn: Option<&str>) -> Result<MethodArn, InvalidMethodArn> {
+    arn.unwrap_or_default().parse()
+}
+
+impl ApiGatewayCustomAuthorizerRequest {
+    /// Parses `method_arn`.
+    pub fn parse_method_arn(&self) -> Result<MethodArn, InvalidMethodArn> {
+        parse(self.method_arn.as_deref())
+    }
+}
+
+impl ApiGatewayCustomAuthorizerRequestTypeRequest {
+    /// Parses `method_arn`.
+    pub fn parse_method_arn(&self) -> Result<MethodArn, InvalidMethodArn> {
+        parse(self.method_arn.as_deref())
+    }
+}
+
+impl ApiGatewayV2CustomAuthorizerV1Request {
+    /// Parses `method_arn`.
+    pub fn parse_method_arn(&self) -> Result<MethodArn, InvalidMethodArn> {
+        parse(self.method_arn.as_deref())
+    }
+}
+
+impl ApiGatewayV2CustomAuthorizerV2Request {
+    /// Parses `route_arn`.
+    pub fn parse_route_arn(&self) -> Result<MethodArn, InvalidMethodArn> {
+        parse(self.route_arn.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate serde_json;
+
+    #[test]
+    fn parse_method_arn() {
+        let arn: MethodArn =
+            "arn:aws:execute-api:us-east-1:123456789012:s4x3opwd6i/test/GET/pets/1"
+                .parse()
+                .unwrap();
+        assert_eq!("aws", arn.partition());
+        assert_eq!("us-east-1", arn.region());
+        assert_eq!("123456789012", arn.account_id());
+        assert_eq!("s4x3opwd6i", arn.api_id());
+        assert_eq!("test", arn.stage());
+        assert_eq!("GET", arn.verb());
+        assert_eq!(Some("pets/1"), arn.path());
+        assert_eq!(
+            "arn:aws:execute-api:us-east-1:123456789012:s4x3opwd6i/*/GET/*",
+            arn.any_stage().any_path().to_string()
+        );
+    }
+
+    #[test]
+    fn method_arn_round_trip() {
+        for arn in &[
+            "arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/*/GET/",
+            "arn:aws:execute-api:eu-west-1:123456789012:abcdef123/test/$connect",
+            "arn:aws-cn:execute-api:cn-north-1:123456789012:abcdef123/prod/POST/a/b/c",
+        ] {
+            let parsed: MethodArn = arn.parse().unwrap();
+            assert_eq!(*arn, parsed.to_string());
+
+            let json = serde_json::to_string(&parsed).unwrap();
+            let reparsed: MethodArn = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, reparsed);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_method_arns() {
+        for arn in &[
+            "",
+            "arn:aws:lambda:us-west-2:123456789012:function:name",
+            "arn:aws:execute-api:us-west-2:123456789012",
+            "arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b",
+            "arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/prod",
+            "ymy8tbxw7b/prod/GET/pets",
+        ] {
+            assert_eq!(
+                Err(InvalidMethodArn(arn.to_string())),
+                arn.parse::<MethodArn>()
+            );
+        }
+        assert!(serde_json::from_str::<MethodArn>("\"arn:aws:s3:::bucket\"").is_err());
+    }
+
+    #[test]
+    fn parse_authorizer_request_arns() {
+        let data = include_bytes!("../fixtures/example-apigw-custom-auth-request.json");
+        let request: ApiGatewayCustomAuthorizerRequest = serde_json::from_slice(data).unwrap();
+        let arn = request.parse_method_arn().unwrap();
+        assert_eq!("ymy8tbxw7b", arn.api_id());
+        assert_eq!("*", arn.stage());
+
+        let data =
+            include_bytes!("../fixtures/example-apigw-v2-custom-authorizer-websocket-request.json");
+        let request: ApiGatewayCustomAuthorizerRequestTypeRequest =
+            serde_json::from_slice(data).unwrap();
+        let arn = request.parse_method_arn().unwrap();
+        assert_eq!("$connect", arn.verb());
+        assert_eq!(None, arn.path());
+
+        let data = include_bytes!("../fixtures/example-apigw-v2-custom-authorizer-v2-request.json");
+        let request: ApiGatewayV2CustomAuthorizerV2Request = serde_json::from_slice(data).unwrap();
+        let arn = request.parse_route_arn().unwrap();
+        assert_eq!("GET", arn.verb());
+        assert_eq!(Some("request"), arn.path());
+
+        let request = ApiGatewayCustomAuthorizerRequest::default();
+        assert!(request.parse_method_arn().is_err());
+    }
+}
@@ -10,6 +10,17 @@ use serde::ser::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
 
+mod authorizer;
+mod connections;
+mod method_arn;
+mod policy;
+mod websocket;
+pub use self::authorizer::*;
+pub use self::connections::*;
+pub use self::method_arn::*;
+pub use self::policy::*;
+pub use self::websocket::*;
+
 /// `ApiGatewayProxyRequest` contains data coming from the API Gateway proxy
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,285 @@
+use super::{
+    ApiGatewayCustomAuthorizerPolicy, ApiGatewayCustomAuthorizerResponse,
+    ApiGatewayV2CustomAuthorizerIamPolicyResponse, IamPolicyStatement, InvalidMethodArn, MethodArn,
+};
+use http::Method;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::fmt;
+
+/// The IAM policy language version used by the generated policies.
+const POLICY_VERSION: &str = "2012-10-17";
+
+/// The only action API Gateway checks in an authorizer policy.
+const INVOKE_ACTION: &str = "execute-api:Invoke";
+
+const WILDCARD: &str = "*";
+
+/// `Effect` is whether an `IamPolicyStatement` allows or denies access.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+impl Effect {
+    /// Returns the value used in the `Effect` field of a policy statement.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Effect::Allow => "Allow",
+            Effect::Deny => "Deny",
+        }
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `ApiGatewayCustomAuthorizerPolicyBuilder` builds the IAM policy returned by a custom authorizer.
+///
+/// Resource ARNs are built from the `method_arn` of the authorizer request, so only the
+/// method and path of each resource need to be given. `*` is a wildcard in paths.
+///
+/// `Effect` only types the policies built here: `IamPolicyStatement::effect` is still a plain
+/// string, so statements built by hand or deserialized from an event aren't checked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApiGatewayCustomAuthorizerPolicyBuilder {
+    method_arn: MethodArn,
+    resources: Vec<(Effect, String, String)>,
+}
+
+impl ApiGatewayCustomAuthorizerPolicyBuilder {
+    /// Creates a builder for the API and stage in `method_arn`.
+    pub fn new(method_arn: &str) -> Result<Self, InvalidMethodArn> {
+        Ok(Self::from_method_arn(method_arn.parse()?))
+    }
+
+    /// Creates a builder for the API and stage of a parsed `MethodArn`.
+    pub fn from_method_arn(method_arn: MethodArn) -> Self {
+        ApiGatewayCustomAuthorizerPolicyBuilder {
+            method_arn,
+            resources: Vec::new(),
+        }
+    }
+
+    /// Builds resources for `stage` instead of the stage in `method_arn`.
+    pub fn stage(mut self, stage: &str) -> Self {
+        self.method_arn = self.method_arn.with_stage(stage);
+        self
+    }
+
+    /// Builds resources that match every stage of the API.
+    pub fn any_stage(self) -> Self {
+        self.stage(WILDCARD)
+    }
+
+    /// Allows `method` requests to `path`.
+    pub fn allow(self, method: Method, path: &str) -> Self {
+        self.resource(Effect::Allow, method.as_str(), path)
+    }
+
+    /// Allows requests to `path` with any method.
+    pub fn allow_any_method(self, path: &str) -> Self {
+        self.resource(Effect::Allow, WILDCARD, path)
+    }
+
+    /// Allows every request to the API.
+    pub fn allow_all(self) -> Self {
+        self.resource(Effect::Allow, WILDCARD, WILDCARD)
+    }
+
+    /// Denies `method` requests to `path`.
+    pub fn deny(self, method: Method, path: &str) -> Self {
+        self.resource(Effect::Deny, method.as_str(), path)
+    }
+
+    /// Denies requests to `path` with any method.
+    pub fn deny_any_method(self, path: &str) -> Self {
+        self.resource(Effect::Deny, WILDCARD, path)
+    }
+
+    /// Denies every request to the API.
+    pub fn deny_all(self) -> Self {
+        self.resource(Effect::Deny, WILDCARD, WILDCARD)
+    }
+
+    /// Adds a resource to the statement with the given `effect`.
+    pub fn resource(mut self, effect: Effect, method: &str, path: &str) -> Self {
+        self.resources.push((
+            effect,
+            method.to_string(),
+            path.trim_start_matches('/').to_string(),
+        ));
+        self
+    }
+
+    /// Builds the policy, with one statement for allowed and one for denied resources.
+    pub fn build(self) -> ApiGatewayCustomAuthorizerPolicy {
+        let statement = [Effect::Allow, Effect::Deny]
+            .iter()
+            .filter_map(|effect| {
+                let resource: Vec<String> = self
+                    .resources
+                    .iter()
+                    .filter(|(e, _, _)| e == effect)
+                    .map(|(_, method, path)| {
+                        self.method_arn
+                            .clone()
+                            .with_verb(method)
+                            .with_path(path)
+                            .to_string()
+                    })
+                    .collect();
+                if resource.is_empty() {
+                    return None;
+                }
+                Some(IamPolicyStatement {
+                    action: vec![INVOKE_ACTION.to_string()],
+                    effect: Some(effect.to_string()),
+                    resource,
+                })
+            })
+            .collect();
+
+        ApiGatewayCustomAuthorizerPolicy {
+            version: Some(POLICY_VERSION.to_string()),
+            statement,
+        }
+    }
+
+    /// Builds the response of a REST API custom authorizer.
+    pub fn into_response<T1>(
+        self,
+        principal_id: &str,
+        context: T1,
+    ) -> ApiGatewayCustomAuthorizerResponse<T1>
+    where
+        T1: DeserializeOwned,
+        T1: Serialize,
+    {
+        ApiGatewayCustomAuthorizerResponse {
+            principal_id: Some(principal_id.to_string()),
+            policy_document: self.build(),
+            context,
+            usage_identifier_key: None,
+        }
+    }
+
+    /// Builds the IAM policy response of an HTTP API Lambda authorizer.
+    pub fn into_v2_response<T1>(
+        self,
+        principal_id: &str,
+        context: T1,
+    ) -> ApiGatewayV2CustomAuthorizerIamPolicyResponse<T1>
+    where
+        T1: DeserializeOwned,
+        T1: Serialize,
+    {
+        ApiGatewayV2CustomAuthorizerIamPolicyResponse {
+            principal_id: Some(principal_id.to_string()),
+            policy_document: self.build(),
+            context,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    extern crate serde_json;
+
+    const METHOD_ARN: &str = "arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/prod/GET/pets";
+
+    #[test]
+    fn build_policy_from_method_arn() {
+        let policy = ApiGatewayCustomAuthorizerPolicyBuilder::new(METHOD_ARN)
+            .unwrap()
+            .allow(Method::GET, "/pets/*")
+            .allow(Method::POST, "/pets")
+            .deny_all()
+            .build();
+
+        assert_eq!(Some("2012-10-17"), policy.version.as_deref());
+        assert_eq!(2, policy.statement.len());
+
+        let allow = &policy.statement[0];
+        assert_eq!(vec!["execute-api:Invoke"], allow.action);
+        assert_eq!(Some("Allow"), allow.effect.as_deref());
+        assert_eq!(
+            vec![
+                "arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/prod/GET/pets/*",
+                "arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/prod/POST/pets",
+            ],
+            allow.resource
+        );
+
+        let deny = &policy.statement[1];
+        assert_eq!(Some("Deny"), deny.effect.as_deref());
+        assert_eq!(
+            vec!["arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/prod/*/*"],
+            deny.resource
+        );
+    }
+
+    #[test]
+    fn build_policy_with_wildcards() {
+        let policy = ApiGatewayCustomAuthorizerPolicyBuilder::new(METHOD_ARN)
+            .unwrap()
+            .any_stage()
+            .allow_any_method("/")
+            .build();
+
+        assert_eq!(1, policy.statement.len());
+        assert_eq!(
+            vec!["arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/*/*/"],
+            policy.statement[0].resource
+        );
+
+        let policy = ApiGatewayCustomAuthorizerPolicyBuilder::new(
+            "arn:aws-cn:execute-api:cn-north-1:123456789012:abcdef123/test/$connect",
+        )
+        .unwrap()
+        .stage("dev")
+        .deny_any_method("admin")
+        .build();
+        assert_eq!(
+            vec!["arn:aws-cn:execute-api:cn-north-1:123456789012:abcdef123/dev/*/admin"],
+            policy.statement[0].resource
+        );
+    }
+
+    #[test]
+    fn build_authorizer_responses() {
+        let response = ApiGatewayCustomAuthorizerPolicyBuilder::new(METHOD_ARN)
+            .unwrap()
+            .allow_all()
+            .into_response("user", json!({"tenant": "a"}));
+        assert_eq!(Some("user"), response.principal_id.as_deref());
+        assert_eq!("a", response.context["tenant"]);
+
+        let output = serde_json::to_value(&response).unwrap();
+        assert_eq!("Allow", output["policyDocument"]["Statement"][0]["Effect"]);
+
+        let response = ApiGatewayCustomAuthorizerPolicyBuilder::new(METHOD_ARN)
+            .unwrap()
+            .allow_all()
+            .into_v2_response("user", Value::Null);
+        assert_eq!(Some("user"), response.principal_id.as_deref());
+        assert_eq!(
+            vec!["arn:aws:execute-api:us-west-2:123456789012:ymy8tbxw7b/prod/*/*"],
+            response.policy_document.statement[0].resource
+        );
+    }
+
+    #[test]
+    fn reject_invalid_method_arns() {
+        let builder =
+            ApiGatewayCustomAuthorizerPolicyBuilder::new("arn:aws:lambda:us-west-2:1:function:f");
+        assert!(builder.is_err());
+    }
+}
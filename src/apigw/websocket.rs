@@ -0,0 +1,295 @@
+use super::{
+    ApiGatewayProxyResponse, ApiGatewayWebsocketProxyRequest,
+    ApiGatewayWebsocketProxyRequestContext,
+};
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::{Deserialize, DeserializeOwned, IntoDeserializer};
+use serde::ser::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Route key of the route invoked when a client connects.
+pub const CONNECT_ROUTE: &str = "$connect";
+/// Route key of the route invoked when a client disconnects.
+pub const DISCONNECT_ROUTE: &str = "$disconnect";
+/// Route key of the route invoked when no other route matches a message.
+pub const DEFAULT_ROUTE: &str = "$default";
+
+/// `WebSocketEventType` is the `eventType` of a WebSocket request context.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WebSocketEventType {
+    Connect,
+    Message,
+    Disconnect,
+}
+
+/// `WebSocketMessageDirection` is the `messageDirection` of a WebSocket request context.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WebSocketMessageDirection {
+    In,
+    Out,
+}
+
+impl FromStr for WebSocketEventType {
+    type Err = ValueError;
+
+    /// Parses the `eventType` value, using the same names as serde.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let deserializer: StrDeserializer<ValueError> = s.into_deserializer();
+        Self::deserialize(deserializer)
+    }
+}
+
+impl FromStr for WebSocketMessageDirection {
+    type Err = ValueError;
+
+    /// Parses the `messageDirection` value, using the same names as serde.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let deserializer: StrDeserializer<ValueError> = s.into_deserializer();
+        Self::deserialize(deserializer)
+    }
+}
+
+impl<T1, T2> ApiGatewayWebsocketProxyRequestContext<T1, T2>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+    T2: DeserializeOwned,
+    T2: Serialize,
+{
+    /// Parses `event_type`, returning `None` when it is missing or unknown.
+    pub fn parse_event_type(&self) -> Option<WebSocketEventType> {
+        self.event_type.as_deref()?.parse().ok()
+    }
+
+    /// Parses `message_direction`, returning `None` when it is missing or unknown.
+    pub fn parse_message_direction(&self) -> Option<WebSocketMessageDirection> {
+        self.message_direction.as_deref()?.parse().ok()
+    }
+}
+
+type Handler<T1, T2> =
+    Box<dyn Fn(ApiGatewayWebsocketProxyRequest<T1, T2>) -> ApiGatewayProxyResponse + Send + Sync>;
+
+/// `WebSocketRouter` dispatches WebSocket API events to a handler by route key.
+///
+/// `CONNECT` and `DISCONNECT` events only go to the `$connect` and `$disconnect` routes,
+/// like API Gateway does. Messages are matched on `route_key`, falling back to the `$default`
+/// route. Requests that match no route get a `404` response.
+pub struct WebSocketRouter<T1 = Value, T2 = Value>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+    T2: DeserializeOwned,
+    T2: Serialize,
+{
+    routes: HashMap<String, Handler<T1, T2>>,
+}
+
+impl<T1, T2> Default for WebSocketRouter<T1, T2>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+    T2: DeserializeOwned,
+    T2: Serialize,
+{
+    fn default() -> Self {
+        WebSocketRouter {
+            routes: HashMap::new(),
+        }
+    }
+}
+
+impl<T1, T2> WebSocketRouter<T1, T2>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+    T2: DeserializeOwned,
+    T2: Serialize,
+{
+    /// Creates a router without routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles requests with the given route key, replacing any previous handler.
+    pub fn route<F>(mut self, route_key: &str, handler: F) -> Self
+    where
+        F: Fn(ApiGatewayWebsocketProxyRequest<T1, T2>) -> ApiGatewayProxyResponse
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.routes.insert(route_key.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Handles the `$connect` route.
+    pub fn on_connect<F>(self, handler: F) -> Self
+    where
+        F: Fn(ApiGatewayWebsocketProxyRequest<T1, T2>) -> ApiGatewayProxyResponse
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.route(CONNECT_ROUTE, handler)
+    }
+
+    /// Handles the `$disconnect` route.
+    pub fn on_disconnect<F>(self, handler: F) -> Self
+    where
+        F: Fn(ApiGatewayWebsocketProxyRequest<T1, T2>) -> ApiGatewayProxyResponse
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.route(DISCONNECT_ROUTE, handler)
+    }
+
+    /// Handles the `$default` route, and every message that matches no other route.
+    pub fn on_default<F>(self, handler: F) -> Self
+    where
+        F: Fn(ApiGatewayWebsocketProxyRequest<T1, T2>) -> ApiGatewayProxyResponse
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.route(DEFAULT_ROUTE, handler)
+    }
+
+    /// Calls the handler for the request's route.
+    pub fn handle(
+        &self,
+        request: ApiGatewayWebsocketProxyRequest<T1, T2>,
+    ) -> ApiGatewayProxyResponse {
+        let context = &request.request_context;
+        let handler = match context.parse_event_type() {
+            Some(WebSocketEventType::Connect) => self.routes.get(CONNECT_ROUTE),
+            Some(WebSocketEventType::Disconnect) => self.routes.get(DISCONNECT_ROUTE),
+            _ => context
+                .route_key
+                .as_deref()
+                .and_then(|key| self.routes.get(key))
+                .or_else(|| self.routes.get(DEFAULT_ROUTE)),
+        };
+
+        match handler {
+            Some(handler) => handler(request),
+            None => ApiGatewayProxyResponse {
+                status_code: 404,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl<T1, T2> fmt::Debug for WebSocketRouter<T1, T2>
+where
+    T1: DeserializeOwned,
+    T1: Serialize,
+    T2: DeserializeOwned,
+    T2: Serialize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WebSocketRouter")
+            .field("routes", &self.routes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encodings::Body;
+
+    extern crate serde_json;
+
+    fn request(route_key: Option<&str>, event_type: &str) -> ApiGatewayWebsocketProxyRequest {
+        let mut request = ApiGatewayWebsocketProxyRequest::default();
+        request.request_context.route_key = route_key.map(String::from);
+        request.request_context.event_type = Some(event_type.to_string());
+        request
+    }
+
+    fn respond(
+        body: &'static str,
+    ) -> impl Fn(ApiGatewayWebsocketProxyRequest) -> ApiGatewayProxyResponse {
+        move |_| ApiGatewayProxyResponse {
+            status_code: 200,
+            body: Some(Body::from(body)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_websocket_context_enums() {
+        let data = include_bytes!("../fixtures/example-apigw-websocket-request.json");
+        let parsed: ApiGatewayWebsocketProxyRequest = serde_json::from_slice(data).unwrap();
+        let context = &parsed.request_context;
+        assert_eq!(
+            Some(WebSocketEventType::Connect),
+            context.parse_event_type()
+        );
+        assert_eq!(
+            Some(WebSocketMessageDirection::In),
+            context.parse_message_direction()
+        );
+
+        let event_type: WebSocketEventType = serde_json::from_str("\"DISCONNECT\"").unwrap();
+        assert_eq!(WebSocketEventType::Disconnect, event_type);
+        assert_eq!(
+            "\"MESSAGE\"",
+            serde_json::to_string(&WebSocketEventType::Message).unwrap()
+        );
+
+        assert_eq!(Ok(WebSocketEventType::Message), "MESSAGE".parse());
+        assert_eq!(Ok(WebSocketMessageDirection::Out), "OUT".parse());
+        assert!("connect".parse::<WebSocketEventType>().is_err());
+        assert!("SIDEWAYS".parse::<WebSocketMessageDirection>().is_err());
+    }
+
+    #[test]
+    fn route_websocket_requests() {
+        let router = WebSocketRouter::new()
+            .on_connect(respond("connect"))
+            .on_disconnect(respond("disconnect"))
+            .on_default(respond("default"))
+            .route("sendmessage", respond("sendmessage"));
+
+        let body = |req| router.handle(req).body;
+        assert_eq!(
+            Some(Body::from("connect")),
+            body(request(Some("$connect"), "CONNECT"))
+        );
+        assert_eq!(
+            Some(Body::from("disconnect")),
+            body(request(None, "DISCONNECT"))
+        );
+        assert_eq!(
+            Some(Body::from("sendmessage")),
+            body(request(Some("sendmessage"), "MESSAGE"))
+        );
+        assert_eq!(
+            Some(Body::from("default")),
+            body(request(Some("unknown"), "MESSAGE"))
+        );
+    }
+
+    #[test]
+    fn unmatched_websocket_route() {
+        let router = WebSocketRouter::new().on_connect(respond("connect"));
+        let res = router.handle(request(Some("sendmessage"), "MESSAGE"));
+        assert_eq!(404, res.status_code);
+        assert_eq!(None, res.body);
+
+        let router = WebSocketRouter::new().on_default(respond("default"));
+        let res = router.handle(request(Some("$connect"), "CONNECT"));
+        assert_eq!(404, res.status_code);
+        let res = router.handle(request(Some("$disconnect"), "DISCONNECT"));
+        assert_eq!(404, res.status_code);
+    }
+}
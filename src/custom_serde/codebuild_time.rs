@@ -1,4 +1,4 @@
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use serde::de::{Deserialize, Deserializer, Error as DeError, Visitor};
 use serde::ser::Serializer;
 use std::fmt;
@@ -15,7 +15,8 @@ impl<'de> Visitor<'de> for TimeVisitor {
     }
 
     fn visit_str<E: DeError>(self, val: &str) -> Result<Self::Value, E> {
-        Utc.datetime_from_str(val, CODEBUILD_TIME_FORMAT)
+        NaiveDateTime::parse_from_str(val, CODEBUILD_TIME_FORMAT)
+            .map(|naive| Utc.from_utc_datetime(&naive))
             .map_err(|e| DeError::custom(format!("Parse error {} for {}", e, val)))
     }
 }
@@ -81,9 +82,7 @@ mod tests {
             "date": "Sep 1, 2017 4:12:29 PM"
         });
 
-        let expected = Utc
-            .datetime_from_str("Sep 1, 2017 4:12:29 PM", CODEBUILD_TIME_FORMAT)
-            .unwrap();
+        let expected = Utc.with_ymd_and_hms(2017, 9, 1, 16, 12, 29).unwrap();
         let decoded: Test = serde_json::from_value(data).unwrap();
         assert_eq!(expected, decoded.date);
     }
@@ -99,9 +98,7 @@ mod tests {
             "date": "Sep 1, 2017 4:12:29 PM"
         });
 
-        let expected = Utc
-            .datetime_from_str("Sep 1, 2017 4:12:29 PM", CODEBUILD_TIME_FORMAT)
-            .unwrap();
+        let expected = Utc.with_ymd_and_hms(2017, 9, 1, 16, 12, 29).unwrap();
         let decoded: Test = serde_json::from_value(data).unwrap();
         assert_eq!(Some(expected), decoded.date);
     }
@@ -34,6 +34,11 @@ pub(crate) mod float_unix_epoch;
 #[cfg(any(feature = "alb", feature = "apigw"))]
 pub(crate) mod http_method;
 
+#[cfg(any(feature = "apigw", feature = "sqs"))]
+mod lenient;
+#[cfg(any(feature = "apigw", feature = "sqs"))]
+pub(crate) use self::lenient::Lenient;
+
 fn normalize_timestamp<'de, D>(deserializer: D) -> Result<(u64, u64), D::Error>
 where
     D: Deserializer<'de>,
@@ -83,10 +88,10 @@ where
 {
     let (whole, frac) = normalize_timestamp(deserializer)?;
     assert_eq!(frac, 0);
-    let seconds: f64 = (whole as f64 / 1000.0) as f64;
+    let seconds: f64 = whole as f64 / 1000.0;
     let milliseconds: u32 = (seconds.fract() * 1000f64) as u32;
     let nanos = milliseconds * 1_000_000;
-    Utc.timestamp_opt(seconds as i64, nanos as u32)
+    Utc.timestamp_opt(seconds as i64, nanos)
         .latest()
         .ok_or_else(|| D::Error::custom("invalid timestamp"))
 }
@@ -217,7 +222,7 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
-    use chrono::TimeZone;
+    use chrono::{TimeZone, Timelike};
     use serde_json;
 
     #[test]
@@ -258,7 +263,11 @@ mod test {
             #[serde(deserialize_with = "deserialize_milliseconds")]
             v: DateTime<Utc>,
         }
-        let expected = Utc.ymd(2017, 10, 05).and_hms_nano(15, 33, 44, 302_000_000);
+        let expected = Utc
+            .with_ymd_and_hms(2017, 10, 5, 15, 33, 44)
+            .unwrap()
+            .with_nanosecond(302_000_000)
+            .unwrap();
 
         // Test parsing strings.
         let data = json!({
@@ -285,7 +294,11 @@ mod test {
             v: DateTime<Utc>,
         }
         let instance = Test {
-            v: Utc.ymd(1983, 7, 22).and_hms_nano(1, 0, 0, 99_888_777),
+            v: Utc
+                .with_ymd_and_hms(1983, 7, 22, 1, 0, 0)
+                .unwrap()
+                .with_nanosecond(99_888_777)
+                .unwrap(),
         };
         let encoded = serde_json::to_string(&instance).unwrap();
         assert_eq!(encoded, String::from(r#"{"v":"427683600099"}"#));
@@ -301,21 +314,33 @@ mod test {
 
         // Make sure nanoseconds are chopped off.
         let instance = Test {
-            v: Utc.ymd(1983, 7, 22).and_hms_nano(1, 0, 0, 99),
+            v: Utc
+                .with_ymd_and_hms(1983, 7, 22, 1, 0, 0)
+                .unwrap()
+                .with_nanosecond(99)
+                .unwrap(),
         };
         let encoded = serde_json::to_string(&instance).unwrap();
         assert_eq!(encoded, String::from(r#"{"v":"427683600"}"#));
 
         // Make sure milliseconds are included.
         let instance = Test {
-            v: Utc.ymd(1983, 7, 22).and_hms_nano(1, 0, 0, 2_000_000),
+            v: Utc
+                .with_ymd_and_hms(1983, 7, 22, 1, 0, 0)
+                .unwrap()
+                .with_nanosecond(2_000_000)
+                .unwrap(),
         };
         let encoded = serde_json::to_string(&instance).unwrap();
         assert_eq!(encoded, String::from(r#"{"v":"427683600.002"}"#));
 
         // Make sure milliseconds are included.
         let instance = Test {
-            v: Utc.ymd(1983, 7, 22).and_hms_nano(1, 0, 0, 1_234_000_000),
+            v: Utc
+                .with_ymd_and_hms(1983, 7, 22, 1, 0, 0)
+                .unwrap()
+                .with_nanosecond(1_234_000_000)
+                .unwrap(),
         };
         let encoded = serde_json::to_string(&instance).unwrap();
         assert_eq!(encoded, String::from(r#"{"v":"427683601.234"}"#));
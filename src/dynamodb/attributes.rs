@@ -137,7 +137,7 @@ mod test {
             AttributeValue::Bs(ref s) => {
                 let expected = vec!["U3Vubnk=", "UmFpbnk=", "U25vd3k="]
                     .into_iter()
-                    .flat_map(|s| base64::decode(&s))
+                    .flat_map(base64::decode)
                     .collect::<Vec<_>>();
                 assert_eq!(&expected, s);
             }
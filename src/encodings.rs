@@ -1,11 +1,11 @@
 use super::custom_serde::*;
 use chrono::{DateTime, Duration, Utc};
-use std::{borrow::Cow, mem::take, ops::Deref, ops::DerefMut, pin::Pin, task::Poll};
+use std::{borrow::Cow, error, fmt, mem::take, ops::Deref, ops::DerefMut, pin::Pin, task::Poll};
 
 use base64::display::Base64Display;
 use bytes::Bytes;
 use http_body::{Body as HttpBody, SizeHint};
-use serde::de::{Deserialize, Deserializer, Error as DeError, Visitor};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer, Error as DeError, Visitor};
 use serde::ser::{Error as SerError, Serialize, Serializer};
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -187,19 +187,89 @@ pub enum Body {
 impl Body {
     /// Decodes body, if needed.
     ///
+    /// Use `try_from_maybe_encoded` to handle malformed bodies without panicking.
+    ///
     /// # Panics
     ///
     /// Panics when aws communicates to handler that request is base64 encoded but
     /// it can not be base64 decoded
     pub fn from_maybe_encoded(is_base64_encoded: bool, body: &str) -> Body {
+        Body::try_from_maybe_encoded(is_base64_encoded, body)
+            .expect("failed to decode aws base64 encoded body")
+    }
+
+    /// Decodes body, if needed.
+    ///
+    /// Returns an error when aws communicates to handler that request is base64 encoded but
+    /// it can not be base64 decoded
+    pub fn try_from_maybe_encoded(is_base64_encoded: bool, body: &str) -> Result<Body, BodyError> {
         if is_base64_encoded {
-            Body::from(::base64::decode(body).expect("failed to decode aws base64 encoded body"))
+            Ok(Body::from(::base64::decode(body)?))
         } else {
-            Body::from(body)
+            Ok(Body::from(body))
+        }
+    }
+
+    /// Deserializes a JSON body into a `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, BodyError> {
+        Ok(serde_json::from_slice(self.as_ref())?)
+    }
+
+    /// Deserializes a form-urlencoded body into a `T`.
+    pub fn form<T: DeserializeOwned>(&self) -> Result<T, BodyError> {
+        Ok(serde_urlencoded::from_bytes(self.as_ref())?)
+    }
+}
+
+/// `BodyError` is returned when a request body can not be decoded or deserialized.
+#[derive(Debug)]
+pub enum BodyError {
+    /// The body was flagged as base64 encoded, but it is not valid base64.
+    Base64(base64::DecodeError),
+    /// The body is not valid JSON for the requested type.
+    Json(serde_json::Error),
+    /// The body is not a valid form-urlencoded string for the requested type.
+    Form(serde_urlencoded::de::Error),
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BodyError::Base64(e) => write!(f, "failed to decode aws base64 encoded body: {}", e),
+            BodyError::Json(e) => write!(f, "failed to deserialize json body: {}", e),
+            BodyError::Form(e) => write!(f, "failed to deserialize form-urlencoded body: {}", e),
+        }
+    }
+}
+
+impl error::Error for BodyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BodyError::Base64(e) => Some(e),
+            BodyError::Json(e) => Some(e),
+            BodyError::Form(e) => Some(e),
         }
     }
 }
 
+impl From<base64::DecodeError> for BodyError {
+    fn from(e: base64::DecodeError) -> Self {
+        BodyError::Base64(e)
+    }
+}
+
+impl From<serde_json::Error> for BodyError {
+    fn from(e: serde_json::Error) -> Self {
+        BodyError::Json(e)
+    }
+}
+
+impl From<serde_urlencoded::de::Error> for BodyError {
+    fn from(e: serde_urlencoded::de::Error) -> Self {
+        BodyError::Form(e)
+    }
+}
+
 impl From<()> for Body {
     fn from(_: ()) -> Self {
         Body::Empty
@@ -447,4 +517,32 @@ mod tests {
             not => panic!("expected Body::Text(...) got {:?}", not),
         }
     }
+
+    #[test]
+    fn try_from_maybe_encoded_invalid_base64() {
+        match Body::try_from_maybe_encoded(true, "not base64!") {
+            Err(BodyError::Base64(_)) => (),
+            not => panic!("expected BodyError::Base64(...) got {:?}", not),
+        }
+    }
+
+    #[test]
+    fn deserialize_json_and_form_bodies() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            a: u32,
+        }
+
+        assert_eq!(Test { a: 1 }, Body::from(r#"{"a":1}"#).json().unwrap());
+        assert_eq!(Test { a: 2 }, Body::from("a=2".as_bytes()).form().unwrap());
+
+        match Body::from(r#"{"a":"one"}"#).json::<Test>() {
+            Err(BodyError::Json(_)) => (),
+            not => panic!("expected BodyError::Json(...) got {:?}", not),
+        }
+        match Body::from("a=one").form::<Test>() {
+            Err(BodyError::Form(_)) => (),
+            not => panic!("expected BodyError::Form(...) got {:?}", not),
+        }
+    }
 }
@@ -0,0 +1,331 @@
+use super::defaults::last_values;
+use super::{
+    build_uri, decode_body, encode_body, join_headers, merge_headers, BinaryMediaTypes,
+    ConversionError, EventDefaults, QUERY_ENCODE_SET,
+};
+use crate::alb::{
+    AlbTargetGroupRequest, AlbTargetGroupRequestContext, AlbTargetGroupResponse, ElbContext,
+};
+use crate::encodings::{Body, BodyError};
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::{HeaderMap, Request, Response, StatusCode};
+use percent_encoding::utf8_percent_encode;
+use query_map::QueryMap;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+impl AlbTargetGroupRequest {
+    /// Returns the request body, decoded from base64 when `is_base64_encoded` is set.
+    pub fn decoded_body(&self) -> Result<Body, BodyError> {
+        decode_body(self.is_base64_encoded, self.body.as_deref())
+    }
+}
+
+/// Converts an ALB target group event into an `http::Request`.
+///
+/// ALB sends query parameters exactly as the client encoded them, so they are
+/// copied into the URI without being encoded a second time.
+/// The `AlbTargetGroupRequestContext` is available in the request extensions.
+impl TryFrom<AlbTargetGroupRequest> for Request<Body> {
+    type Error = ConversionError;
+
+    fn try_from(event: AlbTargetGroupRequest) -> Result<Self, Self::Error> {
+        let headers = merge_headers(&event.headers, &event.multi_value_headers);
+        let query = if event.multi_value_query_string_parameters.is_empty() {
+            raw_query_string(&event.query_string_parameters)
+        } else {
+            raw_query_string(&event.multi_value_query_string_parameters)
+        };
+        let uri = build_uri(&headers, None, event.path.as_deref(), &query);
+        let body = event.decoded_body()?;
+
+        let mut req = Request::builder()
+            .method(event.http_method)
+            .uri(uri)
+            .extension(event.request_context)
+            .body(body)?;
+        *req.headers_mut() = headers;
+        Ok(req)
+    }
+}
+
+/// Converts an `http::Response` into the response expected by an ALB target group
+/// without multi value headers enabled.
+///
+/// Use `AlbTargetGroupResponse::from_http_response` when the target group has
+/// multi value headers enabled.
+impl From<Response<Body>> for AlbTargetGroupResponse {
+    fn from(res: Response<Body>) -> Self {
+        AlbTargetGroupResponse::from_http_response(res, false)
+    }
+}
+
+impl AlbTargetGroupResponse {
+    /// Converts an `http::Response` into the response expected by an ALB target group.
+    ///
+    /// When `multi_value_headers` is `true` the headers are returned in `multi_value_headers`,
+    /// otherwise repeated headers are joined into a single value in `headers`.
+    /// `status_description` is built from the status code and its reason phrase, e.g. `200 OK`.
+    pub fn from_http_response(res: Response<Body>, multi_value_headers: bool) -> Self {
+        let (parts, body) = res.into_parts();
+        let (body, is_base64_encoded) = encode_body(body);
+        let (headers, multi_value_headers) = if multi_value_headers {
+            (Default::default(), parts.headers)
+        } else {
+            (join_headers(parts.headers), Default::default())
+        };
+        AlbTargetGroupResponse {
+            status_code: parts.status.as_u16() as i64,
+            status_description: Some(status_description(parts.status)),
+            headers,
+            multi_value_headers,
+            body,
+            is_base64_encoded,
+        }
+    }
+
+    /// Creates a response with the given `Content-Type` header and body.
+    ///
+    /// `is_base64_encoded` is chosen with `binary_media_types`, so it always matches how the body is encoded.
+    pub fn with_body<B: Into<Body>>(
+        status: StatusCode,
+        content_type: HeaderValue,
+        body: B,
+        binary_media_types: &BinaryMediaTypes,
+    ) -> Self {
+        let (body, is_base64_encoded) =
+            binary_media_types.encode(content_type.to_str().unwrap_or_default(), body.into());
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, content_type);
+        AlbTargetGroupResponse {
+            status_code: status.as_u16() as i64,
+            status_description: Some(status_description(status)),
+            headers,
+            multi_value_headers: Default::default(),
+            body,
+            is_base64_encoded,
+        }
+    }
+}
+
+impl AlbTargetGroupRequest {
+    /// Builds the event that an ALB target group sends for `req`.
+    ///
+    /// Query parameters are kept percent-encoded, like ALB does. Headers and query parameters are
+    /// sent in the multi value fields when `defaults` has multi value headers enabled, otherwise
+    /// only the last value of each is kept.
+    pub fn from_http_request(req: Request<Body>, defaults: &EventDefaults) -> Self {
+        let (parts, body) = req.into_parts();
+        let host = defaults.host(
+            &parts,
+            &format!(
+                "lambda-alb-{}.{}.elb.amazonaws.com",
+                defaults.api_id, defaults.region
+            ),
+        );
+        let headers = defaults.forwarded_headers(&parts, &host);
+        let (body, is_base64_encoded) = defaults.encode_body(&headers, body);
+        let query = raw_query_map(parts.uri.query(), defaults.multi_value_headers);
+        let target_group_arn = format!(
+            "arn:aws:elasticloadbalancing:{}:{}:targetgroup/lambda-target/{}",
+            defaults.region, defaults.account_id, defaults.api_id
+        );
+
+        let (headers, multi_value_headers, query_string_parameters, multi_value_query) =
+            if defaults.multi_value_headers {
+                (HeaderMap::new(), headers, QueryMap::default(), query)
+            } else {
+                (
+                    last_values(&headers),
+                    HeaderMap::new(),
+                    query,
+                    QueryMap::default(),
+                )
+            };
+        AlbTargetGroupRequest {
+            http_method: parts.method,
+            path: Some(parts.uri.path().to_string()),
+            query_string_parameters,
+            multi_value_query_string_parameters: multi_value_query,
+            headers,
+            multi_value_headers,
+            request_context: AlbTargetGroupRequestContext {
+                elb: ElbContext {
+                    target_group_arn: Some(target_group_arn),
+                },
+            },
+            is_base64_encoded,
+            body,
+        }
+    }
+}
+
+/// Splits a query string without decoding it, keeping every value of repeated
+/// parameters when `multi_value` is set, or the last one otherwise.
+fn raw_query_map(query: Option<&str>, multi_value: bool) -> QueryMap {
+    let mut params: HashMap<String, Vec<String>> = HashMap::new();
+    for pair in query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|p| !p.is_empty())
+    {
+        let mut pair = pair.splitn(2, '=');
+        let key = pair.next().unwrap_or_default().to_string();
+        let value = pair.next().unwrap_or_default().to_string();
+        let values = params.entry(key).or_default();
+        if !multi_value {
+            values.clear();
+        }
+        values.push(value);
+    }
+    QueryMap::from(params)
+}
+
+fn status_description(status: StatusCode) -> String {
+    match status.canonical_reason() {
+        Some(reason) => format!("{} {}", status.as_u16(), reason),
+        None => status.as_u16().to_string(),
+    }
+}
+
+pub(super) fn raw_query_string(query: &QueryMap) -> String {
+    query
+        .iter()
+        .map(|(k, v)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(k, QUERY_ENCODE_SET),
+                utf8_percent_encode(v, QUERY_ENCODE_SET)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::alb::AlbTargetGroupRequestContext;
+    use http::header::SET_COOKIE;
+    use http::Method;
+
+    extern crate serde_json;
+
+    #[test]
+    fn example_alb_request_into_http_request() {
+        let data =
+            include_bytes!("../fixtures/example-alb-lambda-target-request-multivalue-headers.json");
+        let event: AlbTargetGroupRequest = serde_json::from_slice(data).unwrap();
+        let req = Request::try_from(event).unwrap();
+
+        assert_eq!(Method::GET, req.method());
+        assert_eq!(
+            "http://lambda-test-alb-1234567.us-east-1.elb.amazonaws.com/?key=hello",
+            req.uri().to_string()
+        );
+        assert_eq!("123", req.headers()["x-myheader"]);
+        assert_eq!(&Body::from("Some text"), req.body());
+
+        let context = req.extensions().get::<AlbTargetGroupRequestContext>();
+        assert!(context.unwrap().elb.target_group_arn.is_some());
+    }
+
+    #[test]
+    fn alb_request_keeps_encoded_query_values() {
+        let event: AlbTargetGroupRequest = serde_json::from_value(json!({
+            "httpMethod": "GET",
+            "path": "/search",
+            "queryStringParameters": {"q": "caf%C3%A9%20au%20lait"},
+            "requestContext": {"elb": {}},
+            "isBase64Encoded": false
+        }))
+        .unwrap();
+        let req = Request::try_from(event).unwrap();
+
+        assert_eq!("q=caf%C3%A9%20au%20lait", req.uri().query().unwrap());
+        let query: QueryMap = req.uri().query().unwrap().parse().unwrap();
+        assert_eq!(Some("café au lait"), query.first("q"));
+    }
+
+    #[test]
+    fn http_response_into_alb_response() {
+        let res = Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(SET_COOKIE, "a=1")
+            .header(SET_COOKIE, "b=2")
+            .body(Body::from("missing"))
+            .unwrap();
+        let res = AlbTargetGroupResponse::from_http_response(res, true);
+
+        assert_eq!(404, res.status_code);
+        assert_eq!(Some("404 Not Found"), res.status_description.as_deref());
+        assert!(res.headers.is_empty());
+        assert_eq!(
+            2,
+            res.multi_value_headers.get_all(SET_COOKIE).iter().count()
+        );
+        assert_eq!(Some(Body::from("missing")), res.body);
+        assert!(!res.is_base64_encoded);
+
+        let res = Response::new(Body::from("ok".as_bytes()));
+        let res = AlbTargetGroupResponse::from(res);
+        assert_eq!(Some("200 OK"), res.status_description.as_deref());
+        assert!(res.multi_value_headers.is_empty());
+        assert!(res.is_base64_encoded);
+    }
+
+    #[test]
+    fn alb_response_with_body() {
+        let res = AlbTargetGroupResponse::with_body(
+            StatusCode::OK,
+            HeaderValue::from_static("text/plain"),
+            "hello".as_bytes(),
+            &BinaryMediaTypes::default(),
+        );
+        assert_eq!(Some("200 OK"), res.status_description.as_deref());
+        assert_eq!("text/plain", res.headers[CONTENT_TYPE]);
+        assert_eq!(Some(Body::from("hello")), res.body);
+        assert!(!res.is_base64_encoded);
+    }
+
+    #[test]
+    fn alb_request_from_http_request() {
+        let req = Request::get("/lambda?q=a%20b&q=c")
+            .header("accept", "text/html")
+            .header("accept", "application/json")
+            .body(Body::Empty)
+            .unwrap();
+        let event = AlbTargetGroupRequest::from_http_request(req, &EventDefaults::new());
+        assert_eq!(Some("c"), event.query_string_parameters.first("q"));
+        assert_eq!("application/json", event.headers["accept"]);
+        assert!(event.multi_value_headers.is_empty());
+        assert_eq!(None, event.body);
+        assert_eq!(
+            Some("arn:aws:elasticloadbalancing:us-east-1:123456789012:targetgroup/lambda-target/1234567890"),
+            event.request_context.elb.target_group_arn.as_deref()
+        );
+
+        let req = Request::get("/lambda?q=a%20b&q=c")
+            .header("accept", "text/html")
+            .header("accept", "application/json")
+            .body(Body::Empty)
+            .unwrap();
+        let defaults = EventDefaults::new().multi_value_headers(true);
+        let event = AlbTargetGroupRequest::from_http_request(req, &defaults);
+        assert!(event.headers.is_empty());
+        assert_eq!(
+            2,
+            event.multi_value_headers.get_all("accept").iter().count()
+        );
+        assert_eq!(
+            Some(vec!["a%20b", "c"]),
+            event.multi_value_query_string_parameters.all("q")
+        );
+
+        let req = Request::try_from(event).unwrap();
+        assert_eq!(
+            "https://lambda-alb-1234567890.us-east-1.elb.amazonaws.com/lambda?q=a%20b&q=c",
+            req.uri().to_string()
+        );
+    }
+}
@@ -0,0 +1,630 @@
+use super::defaults::{
+    domain_prefix, format_request_time, last_query_values, last_values, parse_query, take_cookies,
+    user_agent,
+};
+use super::{
+    build_uri, decode_body, encode_body, encode_body_for_content_type, insert_cookies,
+    join_headers, merge_headers, take_set_cookies, BinaryMediaTypes, ConversionError,
+    EventDefaults,
+};
+use crate::apigw::{
+    ApiGatewayProxyRequest, ApiGatewayProxyRequestContext, ApiGatewayProxyResponse,
+    ApiGatewayRequestIdentity, ApiGatewayV2httpRequest, ApiGatewayV2httpRequestContext,
+    ApiGatewayV2httpRequestContextHttpDescription, ApiGatewayV2httpResponse,
+    ApiGatewayWebsocketProxyRequest,
+};
+use crate::encodings::{Body, BodyError};
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::{HeaderMap, Method, Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// The resource of a REST API proxy integration that handles every path.
+const PROXY_RESOURCE: &str = "/{proxy+}";
+
+impl<T1> ApiGatewayProxyRequest<T1>
+where
+    T1: DeserializeOwned + Serialize,
+{
+    /// Returns the request body, decoded from base64 when `is_base64_encoded` is set.
+    pub fn decoded_body(&self) -> Result<Body, BodyError> {
+        decode_body(
+            self.is_base64_encoded.unwrap_or_default(),
+            self.body.as_deref(),
+        )
+    }
+}
+
+impl ApiGatewayV2httpRequest {
+    /// Returns the request body, decoded from base64 when `is_base64_encoded` is set.
+    pub fn decoded_body(&self) -> Result<Body, BodyError> {
+        decode_body(self.is_base64_encoded, self.body.as_deref())
+    }
+}
+
+/// Converts a REST API event into an `http::Request`.
+///
+/// The `ApiGatewayProxyRequestContext` is available in the request extensions.
+impl<T1> TryFrom<ApiGatewayProxyRequest<T1>> for Request<Body>
+where
+    T1: DeserializeOwned + Serialize + Send + Sync + 'static,
+{
+    type Error = ConversionError;
+
+    fn try_from(event: ApiGatewayProxyRequest<T1>) -> Result<Self, Self::Error> {
+        let headers = merge_headers(&event.headers, &event.multi_value_headers);
+        let query = if event.multi_value_query_string_parameters.is_empty() {
+            event.query_string_parameters.to_query_string()
+        } else {
+            event.multi_value_query_string_parameters.to_query_string()
+        };
+        let uri = build_uri(
+            &headers,
+            event.request_context.domain_name.as_deref(),
+            event.path.as_deref(),
+            &query,
+        );
+        let body = event.decoded_body()?;
+
+        let mut req = Request::builder()
+            .method(event.http_method)
+            .uri(uri)
+            .extension(event.request_context)
+            .body(body)?;
+        *req.headers_mut() = headers;
+        Ok(req)
+    }
+}
+
+/// Converts a WebSocket API event into an `http::Request`.
+///
+/// Only `$connect` events carry an HTTP method, other events are converted into `GET` requests.
+/// The `ApiGatewayWebsocketProxyRequestContext` is available in the request extensions.
+impl<T1, T2> TryFrom<ApiGatewayWebsocketProxyRequest<T1, T2>> for Request<Body>
+where
+    T1: DeserializeOwned + Serialize + Send + Sync + 'static,
+    T2: DeserializeOwned + Serialize + Send + Sync + 'static,
+{
+    type Error = ConversionError;
+
+    fn try_from(event: ApiGatewayWebsocketProxyRequest<T1, T2>) -> Result<Self, Self::Error> {
+        let headers = merge_headers(&event.headers, &event.multi_value_headers);
+        let query = if event.multi_value_query_string_parameters.is_empty() {
+            event.query_string_parameters.to_query_string()
+        } else {
+            event.multi_value_query_string_parameters.to_query_string()
+        };
+        let uri = build_uri(
+            &headers,
+            event.request_context.domain_name.as_deref(),
+            event.path.as_deref(),
+            &query,
+        );
+        let body = decode_body(
+            event.is_base64_encoded.unwrap_or_default(),
+            event.body.as_deref(),
+        )?;
+
+        let mut req = Request::builder()
+            .method(event.http_method.unwrap_or(Method::GET))
+            .uri(uri)
+            .extension(event.request_context)
+            .body(body)?;
+        *req.headers_mut() = headers;
+        Ok(req)
+    }
+}
+
+/// Converts an `http::Response` into the response expected by a REST API.
+impl From<Response<Body>> for ApiGatewayProxyResponse {
+    fn from(res: Response<Body>) -> Self {
+        let (parts, body) = res.into_parts();
+        let (body, is_base64_encoded) = encode_body(body);
+        ApiGatewayProxyResponse {
+            status_code: parts.status.as_u16() as i64,
+            headers: parts.headers.clone(),
+            multi_value_headers: parts.headers,
+            body,
+            is_base64_encoded: Some(is_base64_encoded),
+        }
+    }
+}
+
+impl ApiGatewayProxyResponse {
+    /// Creates a response with the given `Content-Type` header and body.
+    ///
+    /// `is_base64_encoded` is chosen with `binary_media_types`, so it always matches how the body is encoded.
+    pub fn with_body<B: Into<Body>>(
+        status: StatusCode,
+        content_type: HeaderValue,
+        body: B,
+        binary_media_types: &BinaryMediaTypes,
+    ) -> Self {
+        let (body, is_base64_encoded) =
+            binary_media_types.encode(content_type.to_str().unwrap_or_default(), body.into());
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, content_type);
+        ApiGatewayProxyResponse {
+            status_code: status.as_u16() as i64,
+            headers: headers.clone(),
+            multi_value_headers: headers,
+            body,
+            is_base64_encoded: Some(is_base64_encoded),
+        }
+    }
+}
+
+/// Converts an HTTP API (payload format 2.0) event into an `http::Request`.
+///
+/// The `Cookie` header is rebuilt from `cookies`, and the query is taken from
+/// `raw_query_string` so repeated parameters are kept.
+/// The `ApiGatewayV2httpRequestContext` is available in the request extensions.
+impl TryFrom<ApiGatewayV2httpRequest> for Request<Body> {
+    type Error = ConversionError;
+
+    fn try_from(event: ApiGatewayV2httpRequest) -> Result<Self, Self::Error> {
+        let body = event.decoded_body()?;
+        let mut headers = event.headers;
+        insert_cookies(&mut headers, event.cookies.as_deref())?;
+        let uri = build_uri(
+            &headers,
+            event.request_context.domain_name.as_deref(),
+            event.raw_path.as_deref(),
+            event.raw_query_string.as_deref().unwrap_or_default(),
+        );
+
+        let mut req = Request::builder()
+            .method(event.request_context.http.method.clone())
+            .uri(uri)
+            .extension(event.request_context)
+            .body(body)?;
+        *req.headers_mut() = headers;
+        Ok(req)
+    }
+}
+
+/// Converts an `http::Response` into the response expected by an HTTP API.
+///
+/// `Set-Cookie` headers are moved into `cookies`, and the body is base64
+/// encoded unless the `Content-Type` header describes text.
+impl From<Response<Body>> for ApiGatewayV2httpResponse {
+    fn from(res: Response<Body>) -> Self {
+        let (mut parts, body) = res.into_parts();
+        let cookies = take_set_cookies(&mut parts.headers);
+        let (body, is_base64_encoded) = encode_body_for_content_type(&parts.headers, body);
+        ApiGatewayV2httpResponse {
+            status_code: parts.status.as_u16() as i64,
+            headers: join_headers(parts.headers),
+            multi_value_headers: Default::default(),
+            body,
+            is_base64_encoded: Some(is_base64_encoded),
+            cookies,
+        }
+    }
+}
+
+impl ApiGatewayV2httpResponse {
+    /// Creates a response with the given `Content-Type` header and body.
+    ///
+    /// `is_base64_encoded` is chosen with `binary_media_types`, so it always matches how the body is encoded.
+    pub fn with_body<B: Into<Body>>(
+        status: StatusCode,
+        content_type: HeaderValue,
+        body: B,
+        binary_media_types: &BinaryMediaTypes,
+    ) -> Self {
+        let (body, is_base64_encoded) =
+            binary_media_types.encode(content_type.to_str().unwrap_or_default(), body.into());
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, content_type);
+        ApiGatewayV2httpResponse {
+            status_code: status.as_u16() as i64,
+            headers,
+            multi_value_headers: Default::default(),
+            body,
+            is_base64_encoded: Some(is_base64_encoded),
+            cookies: Vec::new(),
+        }
+    }
+}
+
+impl<T1> ApiGatewayProxyRequest<T1>
+where
+    T1: DeserializeOwned + Serialize,
+{
+    /// Builds the event that a REST API with a `/{proxy+}` proxy resource sends for `req`.
+    ///
+    /// The request context is filled in from `defaults`. The domain name is taken from the
+    /// `Host` header or the URI of the request, falling back to the `execute-api` domain of the API.
+    pub fn from_http_request(req: Request<Body>, defaults: &EventDefaults) -> Self {
+        let (parts, body) = req.into_parts();
+        let domain_name = defaults.host(
+            &parts,
+            &format!(
+                "{}.execute-api.{}.amazonaws.com",
+                defaults.api_id, defaults.region
+            ),
+        );
+        let headers = defaults.forwarded_headers(&parts, &domain_name);
+        let (body, is_base64_encoded) = defaults.encode_body(&headers, body);
+        let query = parse_query(parts.uri.query());
+        let path = parts.uri.path().to_string();
+        let stage = defaults.stage_or("prod");
+        let time = defaults.now();
+
+        let mut path_parameters = HashMap::new();
+        path_parameters.insert(
+            "proxy".to_string(),
+            path.trim_start_matches('/').to_string(),
+        );
+
+        ApiGatewayProxyRequest {
+            resource: Some(PROXY_RESOURCE.to_string()),
+            path: Some(path.clone()),
+            http_method: parts.method.clone(),
+            headers: last_values(&headers),
+            multi_value_headers: headers.clone(),
+            query_string_parameters: last_query_values(&query),
+            multi_value_query_string_parameters: query,
+            path_parameters,
+            stage_variables: HashMap::new(),
+            request_context: ApiGatewayProxyRequestContext {
+                account_id: Some(defaults.account_id.clone()),
+                resource_id: None,
+                operation_name: None,
+                stage: Some(stage.clone()),
+                domain_prefix: Some(domain_prefix(&domain_name)),
+                domain_name: Some(domain_name),
+                request_id: Some(defaults.next_request_id()),
+                protocol: Some(format!("{:?}", parts.version)),
+                identity: ApiGatewayRequestIdentity {
+                    source_ip: Some(defaults.source_ip.clone()),
+                    user_agent: user_agent(&headers),
+                    ..Default::default()
+                },
+                resource_path: Some(PROXY_RESOURCE.to_string()),
+                path: Some(format!("/{}{}", stage, path)),
+                authorizer: HashMap::new(),
+                http_method: parts.method,
+                request_time: Some(format_request_time(&time)),
+                request_time_epoch: time.timestamp_millis(),
+                apiid: Some(defaults.api_id.clone()),
+            },
+            body,
+            is_base64_encoded: Some(is_base64_encoded),
+        }
+    }
+}
+
+impl ApiGatewayV2httpRequest {
+    /// Builds the event that an HTTP API with a `$default` route sends for `req`.
+    ///
+    /// The request context is filled in from `defaults`. The domain name is taken from the
+    /// `Host` header or the URI of the request, falling back to the `execute-api` domain of the API.
+    /// `Cookie` headers are moved into `cookies`, and repeated headers are joined with commas.
+    pub fn from_http_request(req: Request<Body>, defaults: &EventDefaults) -> Self {
+        let (parts, body) = req.into_parts();
+        let domain_name = defaults.host(
+            &parts,
+            &format!(
+                "{}.execute-api.{}.amazonaws.com",
+                defaults.api_id, defaults.region
+            ),
+        );
+        let mut headers = defaults.forwarded_headers(&parts, &domain_name);
+        let cookies = take_cookies(&mut headers);
+        let (body, is_base64_encoded) = defaults.encode_body(&headers, body);
+        let path = parts.uri.path().to_string();
+        let time = defaults.now();
+
+        ApiGatewayV2httpRequest {
+            version: Some("2.0".to_string()),
+            route_key: Some("$default".to_string()),
+            raw_path: Some(path.clone()),
+            raw_query_string: Some(parts.uri.query().unwrap_or_default().to_string()),
+            cookies,
+            query_string_parameters: parse_query(parts.uri.query()),
+            path_parameters: HashMap::new(),
+            request_context: ApiGatewayV2httpRequestContext {
+                route_key: Some("$default".to_string()),
+                account_id: Some(defaults.account_id.clone()),
+                stage: Some(defaults.stage_or("$default")),
+                request_id: Some(defaults.next_request_id()),
+                authorizer: None,
+                apiid: Some(defaults.api_id.clone()),
+                domain_prefix: Some(domain_prefix(&domain_name)),
+                domain_name: Some(domain_name),
+                time: Some(format_request_time(&time)),
+                time_epoch: time.timestamp_millis(),
+                http: ApiGatewayV2httpRequestContextHttpDescription {
+                    method: parts.method,
+                    path: Some(path),
+                    protocol: Some(format!("{:?}", parts.version)),
+                    source_ip: Some(defaults.source_ip.clone()),
+                    user_agent: user_agent(&headers),
+                },
+                authentication: None,
+            },
+            headers: join_headers(headers),
+            stage_variables: HashMap::new(),
+            body,
+            is_base64_encoded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::apigw::{ApiGatewayProxyRequestContext, ApiGatewayV2httpRequestContext};
+    use chrono::{TimeZone, Utc};
+    use http::header::{CACHE_CONTROL, COOKIE, SET_COOKIE};
+
+    extern crate serde_json;
+
+    #[test]
+    fn example_apigw_request_into_http_request() {
+        let data = include_bytes!("../fixtures/example-apigw-request.json");
+        let event: ApiGatewayProxyRequest = serde_json::from_slice(data).unwrap();
+        let req = Request::try_from(event).unwrap();
+
+        assert_eq!(Method::POST, req.method());
+        assert_eq!(
+            "https://gy415nuibc.execute-api.us-east-1.amazonaws.com/hello/world?name=me",
+            req.uri().to_string()
+        );
+        assert_eq!("application/json", req.headers()[CONTENT_TYPE]);
+        assert_eq!(&Body::from("{\r\n\t\"a\": 1\r\n}"), req.body());
+
+        let context = req.extensions().get::<ApiGatewayProxyRequestContext>();
+        assert_eq!(Some("testStage"), context.unwrap().stage.as_deref());
+    }
+
+    #[test]
+    fn apigw_request_merges_headers_and_decodes_body() {
+        let event: ApiGatewayProxyRequest = serde_json::from_value(json!({
+            "httpMethod": "PUT",
+            "path": "/upload files",
+            "headers": {"x-single": "one"},
+            "multiValueHeaders": {"x-multi": ["a", "b"]},
+            "multiValueQueryStringParameters": {"tag": ["x", "y"]},
+            "requestContext": {"httpMethod": "PUT", "requestTimeEpoch": 0},
+            "body": "aGVsbG8=",
+            "isBase64Encoded": true
+        }))
+        .unwrap();
+        let req = Request::try_from(event).unwrap();
+
+        assert_eq!("/upload%20files?tag=x&tag=y", req.uri().to_string());
+        assert_eq!("one", req.headers()["x-single"]);
+        let multi: Vec<_> = req.headers().get_all("x-multi").iter().collect();
+        assert_eq!(vec!["a", "b"], multi);
+        assert_eq!(&Body::from("hello".as_bytes()), req.body());
+    }
+
+    #[test]
+    fn apigw_decoded_body() {
+        let event = ApiGatewayV2httpRequest {
+            body: Some(r#"{"a":1}"#.into()),
+            ..Default::default()
+        };
+        let body: serde_json::Value = event.decoded_body().unwrap().json().unwrap();
+        assert_eq!(json!({"a": 1}), body);
+
+        let event = ApiGatewayV2httpRequest {
+            body: Some("not base64!".into()),
+            is_base64_encoded: true,
+            ..Default::default()
+        };
+        match event.decoded_body() {
+            Err(BodyError::Base64(_)) => (),
+            not => panic!("expected BodyError::Base64(...) got {:?}", not),
+        }
+    }
+
+    #[test]
+    fn apigw_request_with_invalid_base64_body() {
+        let event = ApiGatewayProxyRequest::<serde_json::Value> {
+            body: Some("not base64!".into()),
+            is_base64_encoded: Some(true),
+            ..Default::default()
+        };
+        match Request::try_from(event) {
+            Err(ConversionError::InvalidBody(_)) => (),
+            not => panic!("expected ConversionError::InvalidBody got {:?}", not),
+        }
+    }
+
+    #[test]
+    fn http_response_into_apigw_response() {
+        let res = Response::builder()
+            .status(StatusCode::CREATED)
+            .header(SET_COOKIE, "a=1")
+            .header(SET_COOKIE, "b=2")
+            .body(Body::from("ok".as_bytes()))
+            .unwrap();
+        let res = ApiGatewayProxyResponse::from(res);
+
+        assert_eq!(201, res.status_code);
+        assert_eq!(
+            2,
+            res.multi_value_headers.get_all(SET_COOKIE).iter().count()
+        );
+        assert_eq!(Some(Body::from("ok".as_bytes())), res.body);
+        assert_eq!(Some(true), res.is_base64_encoded);
+    }
+
+    #[test]
+    fn example_apigw_v2_request_into_http_request() {
+        let data = include_bytes!("../fixtures/example-apigw-v2-request-jwt-authorizer.json");
+        let event: ApiGatewayV2httpRequest = serde_json::from_slice(data).unwrap();
+        let req = Request::try_from(event).unwrap();
+
+        assert_eq!(Method::GET, req.method());
+        assert_eq!(
+            "https://id.execute-api.us-east-1.amazonaws.com/my/path?parameter1=value1&parameter1=value2&parameter2=value",
+            req.uri().to_string()
+        );
+        assert_eq!("cookie1; cookie2", req.headers()[COOKIE]);
+        assert_eq!("value1", req.headers()["Header1"]);
+        assert_eq!(&Body::from("{\r\n\t\"a\": 1\r\n}"), req.body());
+
+        let context = req.extensions().get::<ApiGatewayV2httpRequestContext>();
+        assert_eq!(Some("id"), context.unwrap().request_id.as_deref());
+    }
+
+    #[test]
+    fn http_response_into_apigw_v2_response() {
+        let res = Response::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .header(CACHE_CONTROL, "no-cache")
+            .header(CACHE_CONTROL, "no-store")
+            .header(SET_COOKIE, "a=1")
+            .header(SET_COOKIE, "b=2; Secure")
+            .body(Body::from("{}".as_bytes()))
+            .unwrap();
+        let res = ApiGatewayV2httpResponse::from(res);
+
+        assert_eq!(200, res.status_code);
+        assert_eq!(vec!["a=1", "b=2; Secure"], res.cookies);
+        assert!(!res.headers.contains_key(SET_COOKIE));
+        assert_eq!("no-cache, no-store", res.headers[CACHE_CONTROL]);
+        assert_eq!(Some(Body::from("{}")), res.body);
+        assert_eq!(Some(false), res.is_base64_encoded);
+
+        let res = Response::builder()
+            .header(CONTENT_TYPE, "image/png")
+            .body(Body::from("png"))
+            .unwrap();
+        let res = ApiGatewayV2httpResponse::from(res);
+        assert_eq!(Some(Body::from("png".as_bytes())), res.body);
+        assert_eq!(Some(true), res.is_base64_encoded);
+    }
+
+    #[test]
+    fn apigw_responses_with_body() {
+        let media_types = BinaryMediaTypes::new(vec!["image/*"]);
+        let res = ApiGatewayProxyResponse::with_body(
+            StatusCode::OK,
+            HeaderValue::from_static("image/png"),
+            "png",
+            &media_types,
+        );
+        assert_eq!("image/png", res.headers[CONTENT_TYPE]);
+        assert_eq!(Some(Body::from("png".as_bytes())), res.body);
+        assert_eq!(Some(true), res.is_base64_encoded);
+
+        let res = ApiGatewayV2httpResponse::with_body(
+            StatusCode::OK,
+            HeaderValue::from_static("application/json"),
+            "{}".as_bytes(),
+            &BinaryMediaTypes::default(),
+        );
+        assert_eq!(Some(Body::from("{}")), res.body);
+        assert_eq!(Some(false), res.is_base64_encoded);
+    }
+
+    fn defaults() -> EventDefaults {
+        EventDefaults::new()
+            .request_id("c6af9ac6-7b61-11e6-9a41-93e8deadbeef")
+            .time(Utc.timestamp_millis_opt(1428582896000).unwrap())
+            .source_ip("192.0.2.1")
+    }
+
+    #[test]
+    fn apigw_request_from_http_request() {
+        let req = Request::post("/hello/world?name=me&name=you")
+            .header("user-agent", "test")
+            .header("accept", "text/html")
+            .header("accept", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let event: ApiGatewayProxyRequest =
+            ApiGatewayProxyRequest::from_http_request(req, &defaults());
+
+        assert_eq!(Some("/{proxy+}"), event.resource.as_deref());
+        assert_eq!(Some("/hello/world"), event.path.as_deref());
+        assert_eq!(
+            Some("hello/world"),
+            event.path_parameters.get("proxy").map(String::as_str)
+        );
+        assert_eq!(Some("you"), event.query_string_parameters.first("name"));
+        assert_eq!(
+            Some(vec!["me", "you"]),
+            event.multi_value_query_string_parameters.all("name")
+        );
+        assert_eq!("application/json", event.headers["accept"]);
+        assert_eq!(
+            2,
+            event.multi_value_headers.get_all("accept").iter().count()
+        );
+        assert_eq!(Some("{}"), event.body.as_deref());
+        assert_eq!(Some(false), event.is_base64_encoded);
+
+        let context = &event.request_context;
+        assert_eq!(
+            Some("c6af9ac6-7b61-11e6-9a41-93e8deadbeef"),
+            context.request_id.as_deref()
+        );
+        assert_eq!(Some("prod"), context.stage.as_deref());
+        assert_eq!(Some("/prod/hello/world"), context.path.as_deref());
+        assert_eq!(
+            Some("09/Apr/2015:12:34:56 +0000"),
+            context.request_time.as_deref()
+        );
+        assert_eq!(1428582896000, context.request_time_epoch);
+        assert_eq!(Some("192.0.2.1"), context.identity.source_ip.as_deref());
+        assert_eq!(Some("test"), context.identity.user_agent.as_deref());
+        assert_eq!(
+            Some("1234567890.execute-api.us-east-1.amazonaws.com"),
+            context.domain_name.as_deref()
+        );
+
+        let json = serde_json::to_vec(&event).unwrap();
+        let reparsed: ApiGatewayProxyRequest = serde_json::from_slice(&json).unwrap();
+        let req = Request::try_from(reparsed).unwrap();
+        assert_eq!(Method::POST, req.method());
+        assert_eq!(
+            "https://1234567890.execute-api.us-east-1.amazonaws.com/hello/world?name=me&name=you",
+            req.uri().to_string()
+        );
+        assert_eq!(&Body::from("{}"), req.body());
+    }
+
+    #[test]
+    fn apigw_v2_request_from_http_request() {
+        let req = Request::put("http://localhost:3000/upload?a=1")
+            .header(COOKIE, "a=1; b=2")
+            .header(CONTENT_TYPE, "image/png")
+            .body(Body::from(vec![0, 159, 146, 150]))
+            .unwrap();
+        let event = ApiGatewayV2httpRequest::from_http_request(req, &defaults().stage("dev"));
+
+        assert_eq!(Some("2.0"), event.version.as_deref());
+        assert_eq!(Some("$default"), event.route_key.as_deref());
+        assert_eq!(Some("/upload"), event.raw_path.as_deref());
+        assert_eq!(Some("a=1"), event.raw_query_string.as_deref());
+        assert_eq!(
+            Some(vec!["a=1".to_string(), "b=2".to_string()]),
+            event.cookies
+        );
+        assert!(!event.headers.contains_key(COOKIE));
+        assert_eq!("localhost:3000", event.headers["host"]);
+        assert_eq!(Some("AJ+Slg=="), event.body.as_deref());
+        assert!(event.is_base64_encoded);
+
+        let context = &event.request_context;
+        assert_eq!(Some("dev"), context.stage.as_deref());
+        assert_eq!(Some("localhost:3000"), context.domain_name.as_deref());
+        assert_eq!(Some("localhost"), context.domain_prefix.as_deref());
+        assert_eq!(Method::PUT, context.http.method);
+        assert_eq!(Some("HTTP/1.1"), context.http.protocol.as_deref());
+
+        let req = Request::try_from(event).unwrap();
+        assert_eq!("http://localhost:3000/upload?a=1", req.uri().to_string());
+        assert_eq!("a=1; b=2", req.headers()[COOKIE]);
+        assert_eq!(&Body::from(vec![0, 159, 146, 150]), req.body());
+    }
+}
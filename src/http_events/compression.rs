@@ -0,0 +1,300 @@
+use super::response::add_vary;
+use super::HttpResponseEvent;
+use crate::encodings::Body;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use http::header::{HeaderValue, ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH};
+use http::HeaderMap;
+use std::io::Write;
+
+/// Bodies smaller than this are not worth compressing.
+const DEFAULT_MIN_SIZE: usize = 1024;
+
+/// `ContentEncoding` is a compression format that can be negotiated with `Accept-Encoding`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContentEncoding {
+    /// `gzip`
+    Gzip,
+    /// `deflate`, the zlib format
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Picks the preferred encoding from an `Accept-Encoding` header value,
+    /// honouring quality values. `gzip` wins ties.
+    pub fn negotiate(accept_encoding: &str) -> Option<ContentEncoding> {
+        let (mut gzip, mut deflate, mut any) = (None, None, None);
+        for entry in accept_encoding.split(',') {
+            let mut params = entry.split(';');
+            let coding = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            let quality = params
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map(|q| q.trim().parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            match coding.as_str() {
+                "gzip" | "x-gzip" => gzip = Some(quality),
+                "deflate" => deflate = Some(quality),
+                "*" => any = Some(quality),
+                _ => {}
+            }
+        }
+        let gzip = gzip.or(any).unwrap_or(0.0);
+        let deflate = deflate.or(any).unwrap_or(0.0);
+        if gzip > 0.0 && gzip >= deflate {
+            Some(ContentEncoding::Gzip)
+        } else if deflate > 0.0 {
+            Some(ContentEncoding::Deflate)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the `Content-Encoding` header value for this encoding.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// `Compression` compresses response bodies with an encoding accepted by the client.
+///
+/// Compressed bodies are returned as `Body::Binary` with `is_base64_encoded` set,
+/// so API Gateway decodes them before sending them to the client.
+#[derive(Clone, Copy, Debug)]
+pub struct Compression {
+    min_size: usize,
+    level: flate2::Compression,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            min_size: DEFAULT_MIN_SIZE,
+            level: flate2::Compression::default(),
+        }
+    }
+}
+
+impl Compression {
+    /// Creates a `Compression` that skips bodies smaller than 1024 bytes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the size, in bytes, below which bodies are sent uncompressed.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Sets the compression level, from 0 (none) to 9 (best).
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = flate2::Compression::new(level.min(9));
+        self
+    }
+
+    /// Compresses the body of `response` when the originating request accepts
+    /// `gzip` or `deflate`, returning the encoding that was applied.
+    ///
+    /// Responses that already have a `Content-Encoding`, that set `Cache-Control: no-transform`,
+    /// or whose body is smaller than the minimum size are left untouched.
+    pub fn compress<R: HttpResponseEvent>(
+        &self,
+        request_headers: &HeaderMap,
+        response: &mut R,
+    ) -> Option<ContentEncoding> {
+        let encoding = request_headers
+            .get_all(ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        let encoding = ContentEncoding::negotiate(&encoding)?;
+
+        let no_transform = match response
+            .header(&CACHE_CONTROL)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(v) => v.to_ascii_lowercase().contains("no-transform"),
+            None => false,
+        };
+        if no_transform || response.header(&CONTENT_ENCODING).is_some() {
+            return None;
+        }
+
+        let body = match response.decoded_body().ok()? {
+            Body::Empty => return None,
+            Body::Text(text) => text.into_bytes(),
+            Body::Binary(bytes) => bytes,
+        };
+        if body.len() < self.min_size {
+            return None;
+        }
+        let compressed = self.encode(encoding, &body).ok()?;
+
+        response.set_body(Body::Binary(compressed));
+        response.set_header(
+            CONTENT_ENCODING,
+            HeaderValue::from_static(encoding.as_str()),
+        );
+        response.remove_header(&CONTENT_LENGTH);
+        add_vary(response, "Accept-Encoding");
+        Some(encoding)
+    }
+
+    fn encode(&self, encoding: ContentEncoding, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        match encoding {
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), self.level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            ContentEncoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), self.level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "apigw"))]
+mod test {
+    use super::*;
+    use crate::apigw::{ApiGatewayProxyResponse, ApiGatewayV2httpResponse};
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use http::header::{CONTENT_TYPE, VARY};
+    use std::io::Read;
+
+    fn accept_encoding(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn large_body() -> String {
+        "hello world ".repeat(200)
+    }
+
+    #[test]
+    fn negotiate_content_encoding() {
+        assert_eq!(
+            Some(ContentEncoding::Gzip),
+            ContentEncoding::negotiate("gzip, deflate, br")
+        );
+        assert_eq!(
+            Some(ContentEncoding::Deflate),
+            ContentEncoding::negotiate("gzip;q=0.5, deflate")
+        );
+        assert_eq!(
+            Some(ContentEncoding::Deflate),
+            ContentEncoding::negotiate("gzip;q=0, *")
+        );
+        assert_eq!(Some(ContentEncoding::Gzip), ContentEncoding::negotiate("*"));
+        assert_eq!(None, ContentEncoding::negotiate("br, identity"));
+        assert_eq!(None, ContentEncoding::negotiate(""));
+    }
+
+    #[test]
+    fn compress_api_gateway_proxy_response() {
+        let mut res = ApiGatewayProxyResponse {
+            status_code: 200,
+            body: Some(Body::from(large_body())),
+            is_base64_encoded: Some(false),
+            ..Default::default()
+        };
+        res.headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        res.headers.insert(VARY, HeaderValue::from_static("Origin"));
+
+        let encoding = Compression::new().compress(&accept_encoding("gzip"), &mut res);
+        assert_eq!(Some(ContentEncoding::Gzip), encoding);
+        assert_eq!("gzip", res.headers[CONTENT_ENCODING]);
+        assert_eq!("Origin, Accept-Encoding", res.headers[VARY]);
+        assert_eq!(Some(true), res.is_base64_encoded);
+
+        let compressed = match res.body {
+            Some(Body::Binary(ref bytes)) => bytes.clone(),
+            ref not => panic!("expected Some(Body::Binary(...)) got {:?}", not),
+        };
+        assert!(compressed.len() < large_body().len());
+        let mut decompressed = String::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(large_body(), decompressed);
+    }
+
+    #[test]
+    fn compress_api_gateway_v2_response() {
+        let mut res = ApiGatewayV2httpResponse {
+            status_code: 200,
+            body: Some(Body::from(large_body())),
+            ..Default::default()
+        };
+
+        let encoding = Compression::new().compress(&accept_encoding("deflate"), &mut res);
+        assert_eq!(Some(ContentEncoding::Deflate), encoding);
+        assert_eq!("deflate", res.headers[CONTENT_ENCODING]);
+        assert_eq!("Accept-Encoding", res.headers[VARY]);
+        assert_eq!(Some(true), res.is_base64_encoded);
+
+        let body = res.decoded_body().unwrap();
+        let mut decompressed = String::new();
+        ZlibDecoder::new(body.as_ref())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(large_body(), decompressed);
+    }
+
+    #[test]
+    fn compress_skips_responses() {
+        let response = |body: &str| ApiGatewayV2httpResponse {
+            status_code: 200,
+            body: Some(Body::from(body)),
+            ..Default::default()
+        };
+
+        let mut small = response("hello");
+        assert_eq!(
+            None,
+            Compression::new().compress(&accept_encoding("gzip"), &mut small)
+        );
+        assert_eq!(Some(Body::from("hello")), small.body);
+
+        let mut small = response("hello");
+        let encoding = Compression::new()
+            .min_size(0)
+            .compress(&accept_encoding("gzip"), &mut small);
+        assert_eq!(Some(ContentEncoding::Gzip), encoding);
+
+        let mut not_accepted = response(&large_body());
+        assert_eq!(
+            None,
+            Compression::new().compress(&HeaderMap::new(), &mut not_accepted)
+        );
+
+        let mut encoded = response(&large_body());
+        encoded
+            .headers
+            .insert(CONTENT_ENCODING, HeaderValue::from_static("br"));
+        assert_eq!(
+            None,
+            Compression::new().compress(&accept_encoding("gzip"), &mut encoded)
+        );
+
+        let mut no_transform = response(&large_body());
+        no_transform
+            .headers
+            .insert(CACHE_CONTROL, HeaderValue::from_static("no-transform"));
+        assert_eq!(
+            None,
+            Compression::new().compress(&accept_encoding("gzip"), &mut no_transform)
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use super::response::add_vary;
+use super::HttpResponseEvent;
+use crate::encodings::Body;
+use http::header::{
+    HeaderName, HeaderValue, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+    ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+};
+use http::{HeaderMap, Method, Response, StatusCode};
+use std::time::Duration;
+
+#[cfg(feature = "apigw")]
+use super::merge_headers;
+#[cfg(feature = "apigw")]
+use crate::apigw::{
+    ApiGatewayProxyRequest, ApiGatewayProxyResponse, ApiGatewayV2httpRequest,
+    ApiGatewayV2httpResponse,
+};
+#[cfg(feature = "apigw")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "apigw")]
+use serde::ser::Serialize;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum AllowList<T> {
+    Any,
+    Only(Vec<T>),
+}
+
+impl<T> AllowList<T> {
+    fn push(&mut self, value: T) {
+        match self {
+            AllowList::Any => *self = AllowList::Only(vec![value]),
+            AllowList::Only(values) => values.push(value),
+        }
+    }
+}
+
+/// `Cors` is a Cross-Origin Resource Sharing policy for HTTP events.
+///
+/// `preflight` answers `OPTIONS` preflight requests, and `apply` adds the CORS headers
+/// to any HTTP response event. A policy allows no origin until one is configured,
+/// and allows the `GET`, `HEAD` and `POST` methods by default.
+///
+/// Wildcards are never sent together with `Access-Control-Allow-Credentials`: when
+/// credentials are allowed, the request's origin, method and headers are echoed instead,
+/// with the matching `Vary` headers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cors {
+    origins: AllowList<String>,
+    methods: AllowList<Method>,
+    headers: AllowList<HeaderName>,
+    expose_headers: Vec<HeaderName>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            origins: AllowList::Only(Vec::new()),
+            methods: AllowList::Only(vec![Method::GET, Method::HEAD, Method::POST]),
+            headers: AllowList::Only(Vec::new()),
+            expose_headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+}
+
+impl Cors {
+    /// Creates a policy that allows no origin.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows requests from an origin, e.g. `https://example.com`.
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        self.origins.push(origin.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Allows requests from every origin.
+    pub fn allow_any_origin(mut self) -> Self {
+        self.origins = AllowList::Any;
+        self
+    }
+
+    /// Replaces the allowed methods.
+    pub fn allow_methods<I: IntoIterator<Item = Method>>(mut self, methods: I) -> Self {
+        self.methods = AllowList::Only(methods.into_iter().collect());
+        self
+    }
+
+    /// Allows every method.
+    pub fn allow_any_method(mut self) -> Self {
+        self.methods = AllowList::Any;
+        self
+    }
+
+    /// Replaces the request headers allowed in preflight requests.
+    pub fn allow_headers<I: IntoIterator<Item = HeaderName>>(mut self, headers: I) -> Self {
+        self.headers = AllowList::Only(headers.into_iter().collect());
+        self
+    }
+
+    /// Allows every request header.
+    pub fn allow_any_header(mut self) -> Self {
+        self.headers = AllowList::Any;
+        self
+    }
+
+    /// Replaces the response headers exposed to scripts.
+    pub fn expose_headers<I: IntoIterator<Item = HeaderName>>(mut self, headers: I) -> Self {
+        self.expose_headers = headers.into_iter().collect();
+        self
+    }
+
+    /// Allows requests with credentials: cookies, TLS client certificates or `Authorization` headers.
+    pub fn allow_credentials(mut self, allow: bool) -> Self {
+        self.credentials = allow;
+        self
+    }
+
+    /// Sets how long browsers can cache the result of a preflight request.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns whether a request is a CORS preflight request: an `OPTIONS` request
+    /// with `Origin` and `Access-Control-Request-Method` headers.
+    pub fn is_preflight(method: &Method, request_headers: &HeaderMap) -> bool {
+        method == Method::OPTIONS
+            && request_headers.contains_key(ORIGIN)
+            && request_headers.contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+    }
+
+    /// Answers a preflight request, returning `None` for any other request.
+    ///
+    /// The response is a `204 No Content`. It carries no `Access-Control-Allow-*` headers when
+    /// the origin, the requested method or one of the requested headers is not allowed,
+    /// so the browser rejects the actual request.
+    pub fn preflight(
+        &self,
+        method: &Method,
+        request_headers: &HeaderMap,
+    ) -> Option<Response<Body>> {
+        if !Cors::is_preflight(method, request_headers) {
+            return None;
+        }
+
+        let mut res = Response::new(Body::Empty);
+        *res.status_mut() = StatusCode::NO_CONTENT;
+        if self.varies_by_origin() {
+            add_vary(&mut res, "Origin");
+        }
+
+        let allow_origin = match self.allow_origin_value(request_headers) {
+            Some(allow_origin) => allow_origin,
+            None => return Some(res),
+        };
+        let allow_methods = match self.allow_methods_value(request_headers, &mut res) {
+            Some(allow_methods) => allow_methods,
+            None => return Some(res),
+        };
+        let allow_headers = match self.allow_headers_value(request_headers, &mut res) {
+            Some(allow_headers) => allow_headers,
+            None => return Some(res),
+        };
+
+        let headers = res.headers_mut();
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        headers.insert(ACCESS_CONTROL_ALLOW_METHODS, allow_methods);
+        if let Some(allow_headers) = allow_headers {
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allow_headers);
+        }
+        if self.credentials {
+            headers.insert(
+                ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+        if let Some(max_age) = self.max_age {
+            headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+        }
+        Some(res)
+    }
+
+    /// Answers a REST API preflight request, returning `None` for any other request.
+    #[cfg(feature = "apigw")]
+    pub fn preflight_rest_api<T1>(
+        &self,
+        event: &ApiGatewayProxyRequest<T1>,
+    ) -> Option<ApiGatewayProxyResponse>
+    where
+        T1: DeserializeOwned + Serialize,
+    {
+        let headers = merge_headers(&event.headers, &event.multi_value_headers);
+        self.preflight(&event.http_method, &headers)
+            .map(ApiGatewayProxyResponse::from)
+    }
+
+    /// Answers an HTTP API preflight request, returning `None` for any other request.
+    #[cfg(feature = "apigw")]
+    pub fn preflight_http_api(
+        &self,
+        event: &ApiGatewayV2httpRequest,
+    ) -> Option<ApiGatewayV2httpResponse> {
+        self.preflight(&event.request_context.http.method, &event.headers)
+            .map(ApiGatewayV2httpResponse::from)
+    }
+
+    /// Adds the CORS headers for an actual (non-preflight) request to a response.
+    ///
+    /// Nothing but `Vary: Origin` is added when the request has no `Origin` header
+    /// or its origin is not allowed.
+    pub fn apply<R: HttpResponseEvent>(&self, request_headers: &HeaderMap, response: &mut R) {
+        if self.varies_by_origin() {
+            add_vary(response, "Origin");
+        }
+
+        let allow_origin = match self.allow_origin_value(request_headers) {
+            Some(allow_origin) => allow_origin,
+            None => return,
+        };
+        response.set_header(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if self.credentials {
+            response.set_header(
+                ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+        if let Some(expose_headers) = join(&self.expose_headers) {
+            response.set_header(ACCESS_CONTROL_EXPOSE_HEADERS, expose_headers);
+        }
+    }
+
+    /// `Access-Control-Allow-Origin` depends on the request unless it's a plain wildcard.
+    fn varies_by_origin(&self) -> bool {
+        self.credentials || self.origins != AllowList::Any
+    }
+
+    fn allow_origin_value(&self, request_headers: &HeaderMap) -> Option<HeaderValue> {
+        let origin = request_headers.get(ORIGIN)?;
+        match self.origins {
+            AllowList::Any if !self.credentials => Some(HeaderValue::from_static("*")),
+            AllowList::Any => Some(origin.clone()),
+            AllowList::Only(ref origins) => {
+                let value = origin.to_str().ok()?;
+                if origins.iter().any(|o| o == value) {
+                    Some(origin.clone())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn allow_methods_value(
+        &self,
+        request_headers: &HeaderMap,
+        res: &mut Response<Body>,
+    ) -> Option<HeaderValue> {
+        let requested = request_headers.get(ACCESS_CONTROL_REQUEST_METHOD)?;
+        match self.methods {
+            AllowList::Any if !self.credentials => Some(HeaderValue::from_static("*")),
+            AllowList::Any => {
+                add_vary(res, "Access-Control-Request-Method");
+                Some(requested.clone())
+            }
+            AllowList::Only(ref methods) => {
+                let method = Method::from_bytes(requested.as_bytes()).ok()?;
+                if methods.contains(&method) {
+                    join(methods)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Returns `None` when a requested header is not allowed, and `Some(None)`
+    /// when there is no `Access-Control-Allow-Headers` to send.
+    fn allow_headers_value(
+        &self,
+        request_headers: &HeaderMap,
+        res: &mut Response<Body>,
+    ) -> Option<Option<HeaderValue>> {
+        let requested = request_headers.get(ACCESS_CONTROL_REQUEST_HEADERS);
+        match self.headers {
+            AllowList::Any if !self.credentials => Some(Some(HeaderValue::from_static("*"))),
+            AllowList::Any => {
+                add_vary(res, "Access-Control-Request-Headers");
+                Some(requested.cloned())
+            }
+            AllowList::Only(ref headers) => {
+                let requested = match requested {
+                    Some(requested) => requested.to_str().ok()?,
+                    None => "",
+                };
+                let allowed = requested
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|h| !h.is_empty())
+                    .all(|h| headers.iter().any(|a| a.as_str().eq_ignore_ascii_case(h)));
+                if allowed {
+                    Some(join(headers))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+fn join<T: AsRef<str>>(values: &[T]) -> Option<HeaderValue> {
+    if values.is_empty() {
+        return None;
+    }
+    let joined = values
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::from_str(&joined).ok()
+}
+
+#[cfg(all(test, feature = "apigw"))]
+mod test {
+    use super::*;
+    use http::header::{CONTENT_TYPE, VARY};
+
+    extern crate serde_json;
+
+    fn preflight_headers(origin: &str, method: &str, headers: Option<&str>) -> HeaderMap {
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(ORIGIN, HeaderValue::from_str(origin).unwrap());
+        request_headers.insert(
+            ACCESS_CONTROL_REQUEST_METHOD,
+            HeaderValue::from_str(method).unwrap(),
+        );
+        if let Some(headers) = headers {
+            request_headers.insert(
+                ACCESS_CONTROL_REQUEST_HEADERS,
+                HeaderValue::from_str(headers).unwrap(),
+            );
+        }
+        request_headers
+    }
+
+    #[test]
+    fn preflight_allowed_origin() {
+        let cors = Cors::new()
+            .allow_origin("https://example.com")
+            .allow_methods(vec![Method::GET, Method::PUT])
+            .allow_headers(vec![CONTENT_TYPE])
+            .max_age(Duration::from_secs(600));
+        let headers = preflight_headers("https://example.com", "PUT", Some("content-type"));
+
+        let res = cors.preflight(&Method::OPTIONS, &headers).unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, res.status());
+        let headers = res.headers();
+        assert_eq!("https://example.com", headers[ACCESS_CONTROL_ALLOW_ORIGIN]);
+        assert_eq!("GET, PUT", headers[ACCESS_CONTROL_ALLOW_METHODS]);
+        assert_eq!("content-type", headers[ACCESS_CONTROL_ALLOW_HEADERS]);
+        assert_eq!("600", headers[ACCESS_CONTROL_MAX_AGE]);
+        assert_eq!("Origin", headers[VARY]);
+        assert!(!headers.contains_key(ACCESS_CONTROL_ALLOW_CREDENTIALS));
+    }
+
+    #[test]
+    fn preflight_rejected() {
+        let cors = Cors::new().allow_origin("https://example.com");
+
+        for headers in &[
+            preflight_headers("https://evil.example", "GET", None),
+            preflight_headers("https://EXAMPLE.com", "GET", None),
+            preflight_headers("https://example.com", "DELETE", None),
+            preflight_headers("https://example.com", "GET", Some("x-custom")),
+        ] {
+            let res = cors.preflight(&Method::OPTIONS, headers).unwrap();
+            assert_eq!(StatusCode::NO_CONTENT, res.status());
+            assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+            assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_METHODS));
+            assert_eq!("Origin", res.headers()[VARY]);
+        }
+    }
+
+    #[test]
+    fn not_a_preflight() {
+        let cors = Cors::new().allow_any_origin();
+        let headers = preflight_headers("https://example.com", "GET", None);
+        assert!(cors.preflight(&Method::GET, &headers).is_none());
+        assert!(cors
+            .preflight(&Method::OPTIONS, &HeaderMap::new())
+            .is_none());
+    }
+
+    #[test]
+    fn preflight_wildcards() {
+        let cors = Cors::new()
+            .allow_any_origin()
+            .allow_any_method()
+            .allow_any_header();
+        let headers = preflight_headers("https://example.com", "PATCH", Some("x-custom"));
+
+        let res = cors.preflight(&Method::OPTIONS, &headers).unwrap();
+        let headers = res.headers();
+        assert_eq!("*", headers[ACCESS_CONTROL_ALLOW_ORIGIN]);
+        assert_eq!("*", headers[ACCESS_CONTROL_ALLOW_METHODS]);
+        assert_eq!("*", headers[ACCESS_CONTROL_ALLOW_HEADERS]);
+        assert!(!headers.contains_key(VARY));
+    }
+
+    #[test]
+    fn preflight_wildcards_with_credentials() {
+        let cors = Cors::new()
+            .allow_any_origin()
+            .allow_any_method()
+            .allow_any_header()
+            .allow_credentials(true);
+        let headers = preflight_headers("https://example.com", "PATCH", Some("x-custom"));
+
+        let res = cors.preflight(&Method::OPTIONS, &headers).unwrap();
+        let headers = res.headers();
+        assert_eq!("https://example.com", headers[ACCESS_CONTROL_ALLOW_ORIGIN]);
+        assert_eq!("PATCH", headers[ACCESS_CONTROL_ALLOW_METHODS]);
+        assert_eq!("x-custom", headers[ACCESS_CONTROL_ALLOW_HEADERS]);
+        assert_eq!("true", headers[ACCESS_CONTROL_ALLOW_CREDENTIALS]);
+        assert_eq!(
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+            headers[VARY]
+        );
+    }
+
+    #[test]
+    fn preflight_api_gateway_events() {
+        let cors = Cors::new().allow_origin("https://example.com");
+
+        let event = ApiGatewayProxyRequest::<serde_json::Value> {
+            http_method: Method::OPTIONS,
+            multi_value_headers: preflight_headers("https://example.com", "POST", None),
+            ..Default::default()
+        };
+        let res = cors.preflight_rest_api(&event).unwrap();
+        assert_eq!(204, res.status_code);
+        assert_eq!(
+            "https://example.com",
+            res.headers[ACCESS_CONTROL_ALLOW_ORIGIN]
+        );
+
+        let mut event = ApiGatewayV2httpRequest::default();
+        event.request_context.http.method = Method::OPTIONS;
+        event.headers = preflight_headers("https://example.com", "POST", None);
+        let res = cors.preflight_http_api(&event).unwrap();
+        assert_eq!(204, res.status_code);
+        assert_eq!("GET, HEAD, POST", res.headers[ACCESS_CONTROL_ALLOW_METHODS]);
+
+        event.request_context.http.method = Method::POST;
+        assert!(cors.preflight_http_api(&event).is_none());
+    }
+
+    #[test]
+    fn apply_to_response() {
+        let cors = Cors::new()
+            .allow_origin("https://example.com")
+            .expose_headers(vec![HeaderName::from_static("x-request-id")])
+            .allow_credentials(true);
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(ORIGIN, HeaderValue::from_static("https://example.com"));
+
+        let mut res = ApiGatewayProxyResponse::default();
+        res.headers
+            .insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+        cors.apply(&request_headers, &mut res);
+        assert_eq!(
+            "https://example.com",
+            res.headers[ACCESS_CONTROL_ALLOW_ORIGIN]
+        );
+        assert_eq!("true", res.headers[ACCESS_CONTROL_ALLOW_CREDENTIALS]);
+        assert_eq!("x-request-id", res.headers[ACCESS_CONTROL_EXPOSE_HEADERS]);
+        assert_eq!("Accept-Encoding, Origin", res.headers[VARY]);
+
+        request_headers.insert(ORIGIN, HeaderValue::from_static("https://evil.example"));
+        let mut res = ApiGatewayV2httpResponse::default();
+        cors.apply(&request_headers, &mut res);
+        assert!(!res.headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert_eq!("Origin", res.headers[VARY]);
+    }
+
+    #[test]
+    fn apply_any_origin() {
+        let cors = Cors::new().allow_any_origin();
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(ORIGIN, HeaderValue::from_static("https://example.com"));
+
+        let mut res = Response::new(Body::Empty);
+        cors.apply(&request_headers, &mut res);
+        assert_eq!("*", res.headers()[ACCESS_CONTROL_ALLOW_ORIGIN]);
+        assert!(!res.headers().contains_key(VARY));
+    }
+}
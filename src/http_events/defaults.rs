@@ -0,0 +1,345 @@
+use super::{encode_body, header_str, BinaryMediaTypes};
+use crate::encodings::Body;
+use chrono::{DateTime, Utc};
+use http::header::{HeaderValue, CONTENT_TYPE, HOST};
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+use http::header::{COOKIE, USER_AGENT};
+use http::request::Parts;
+use http::HeaderMap;
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+use query_map::QueryMap;
+#[cfg(feature = "apigw")]
+use std::collections::HashMap;
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+static REQUEST_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// `EventDefaults` holds the values that AWS adds to the HTTP events it sends to Lambda,
+/// used to build events from an `http::Request` when there is no AWS integration in front
+/// of the function, e.g. in integration tests or a local server.
+///
+/// Every request gets a new request id and the current time unless they are configured.
+/// Request bodies are base64 encoded when their `Content-Type` is one of the binary media types,
+/// or when they are not valid UTF-8.
+#[derive(Clone, Debug)]
+pub struct EventDefaults {
+    pub(super) request_id: Option<String>,
+    pub(super) time: Option<DateTime<Utc>>,
+    pub(super) source_ip: String,
+    pub(super) stage: Option<String>,
+    pub(super) account_id: String,
+    pub(super) api_id: String,
+    pub(super) region: String,
+    pub(super) multi_value_headers: bool,
+    pub(super) binary_media_types: BinaryMediaTypes,
+}
+
+impl Default for EventDefaults {
+    fn default() -> Self {
+        EventDefaults {
+            request_id: None,
+            time: None,
+            source_ip: "127.0.0.1".to_string(),
+            stage: None,
+            account_id: "123456789012".to_string(),
+            api_id: "1234567890".to_string(),
+            region: "us-east-1".to_string(),
+            multi_value_headers: false,
+            binary_media_types: BinaryMediaTypes::default(),
+        }
+    }
+}
+
+impl EventDefaults {
+    /// Creates the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the same request id for every request.
+    pub fn request_id(mut self, request_id: &str) -> Self {
+        self.request_id = Some(request_id.to_string());
+        self
+    }
+
+    /// Uses the same request time for every request.
+    pub fn time(mut self, time: DateTime<Utc>) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Sets the IP address of the client, `127.0.0.1` by default.
+    pub fn source_ip(mut self, source_ip: &str) -> Self {
+        self.source_ip = source_ip.to_string();
+        self
+    }
+
+    /// Sets the API Gateway stage, `prod` for REST APIs and `$default` for HTTP APIs by default.
+    pub fn stage(mut self, stage: &str) -> Self {
+        self.stage = Some(stage.to_string());
+        self
+    }
+
+    /// Sets the AWS account that owns the API.
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = account_id.to_string();
+        self
+    }
+
+    /// Sets the API id, or the URL id of a function URL.
+    pub fn api_id(mut self, api_id: &str) -> Self {
+        self.api_id = api_id.to_string();
+        self
+    }
+
+    /// Sets the region used in domain names and ARNs.
+    pub fn region(mut self, region: &str) -> Self {
+        self.region = region.to_string();
+        self
+    }
+
+    /// Sends headers and query parameters to ALB targets in `multi_value_headers`
+    /// and `multi_value_query_string_parameters`, like target groups with multi value headers enabled.
+    pub fn multi_value_headers(mut self, enabled: bool) -> Self {
+        self.multi_value_headers = enabled;
+        self
+    }
+
+    /// Sets the media types of request bodies that are base64 encoded.
+    pub fn binary_media_types(mut self, binary_media_types: BinaryMediaTypes) -> Self {
+        self.binary_media_types = binary_media_types;
+        self
+    }
+
+    #[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+    pub(crate) fn next_request_id(&self) -> String {
+        if let Some(ref request_id) = self.request_id {
+            return request_id.clone();
+        }
+        let now = Utc::now();
+        let counter = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed) as u64;
+        format!(
+            "{:08x}-{:04x}-4{:03x}-8{:03x}-{:012x}",
+            now.timestamp() as u32,
+            (now.timestamp_subsec_nanos() >> 16) & 0xffff,
+            (counter >> 12) & 0xfff,
+            counter & 0xfff,
+            u64::from(now.timestamp_subsec_nanos()) << 16 | (counter & 0xffff),
+        )
+    }
+
+    #[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.time.unwrap_or_else(Utc::now)
+    }
+
+    #[cfg(feature = "apigw")]
+    pub(crate) fn stage_or(&self, default: &str) -> String {
+        self.stage.clone().unwrap_or_else(|| default.to_string())
+    }
+
+    /// Returns the `Host` of the request, falling back to `domain_name`.
+    pub(crate) fn host(&self, parts: &Parts, domain_name: &str) -> String {
+        header_str(&parts.headers, &HOST)
+            .or_else(|| parts.uri.authority().map(|a| a.as_str()))
+            .unwrap_or(domain_name)
+            .to_string()
+    }
+
+    /// Returns the request headers with the `Host` and `X-Forwarded-*` headers
+    /// added by AWS load balancers.
+    pub(crate) fn forwarded_headers(&self, parts: &Parts, host: &str) -> HeaderMap {
+        let mut headers = parts.headers.clone();
+        let https = parts.uri.scheme_str() != Some("http");
+        let port = parts.uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+        let defaults = [
+            ("host", host.to_string()),
+            ("x-forwarded-for", self.source_ip.clone()),
+            (
+                "x-forwarded-proto",
+                if https { "https" } else { "http" }.to_string(),
+            ),
+            ("x-forwarded-port", port.to_string()),
+        ];
+        for (name, value) in defaults.iter() {
+            if !headers.contains_key(*name) {
+                if let Ok(value) = HeaderValue::from_str(value) {
+                    headers.insert(*name, value);
+                }
+            }
+        }
+        headers
+    }
+
+    /// Splits a request body into the `body` and `is_base64_encoded` fields of a request event.
+    pub(crate) fn encode_body(&self, headers: &HeaderMap, body: Body) -> (Option<String>, bool) {
+        let (body, is_base64_encoded) = match header_str(headers, &CONTENT_TYPE) {
+            Some(content_type) => self.binary_media_types.encode(content_type, body),
+            None => encode_body(body),
+        };
+        let body = body.map(|body| match body {
+            Body::Empty => String::new(),
+            Body::Text(text) => text,
+            Body::Binary(bytes) => base64::encode(bytes),
+        });
+        (body, is_base64_encoded)
+    }
+}
+
+/// Returns the `User-Agent` header of a request.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn user_agent(headers: &HeaderMap) -> Option<String> {
+    header_str(headers, &USER_AGENT).map(String::from)
+}
+
+/// Returns the first label of a domain name, e.g. the API id of an `execute-api` domain.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn domain_prefix(domain_name: &str) -> String {
+    domain_name
+        .split(['.', ':'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Keeps the last value of every header, like integrations without multi value headers.
+#[cfg(any(feature = "alb", feature = "apigw"))]
+pub(crate) fn last_values(headers: &HeaderMap) -> HeaderMap {
+    let mut last = HeaderMap::with_capacity(headers.keys_len());
+    for name in headers.keys() {
+        if let Some(value) = headers.get_all(name).iter().next_back() {
+            last.insert(name, value.clone());
+        }
+    }
+    last
+}
+
+/// Keeps the last value of every query parameter, like the `query_string_parameters`
+/// of REST API events.
+#[cfg(feature = "apigw")]
+pub(crate) fn last_query_values(query: &QueryMap) -> QueryMap {
+    let last: HashMap<String, String> = query
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    last.into()
+}
+
+/// Parses and decodes a query string.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn parse_query(query: Option<&str>) -> QueryMap {
+    query
+        .unwrap_or_default()
+        .parse()
+        .unwrap_or_else(|_| QueryMap::default())
+}
+
+/// Formats a request time like API Gateway, e.g. `09/Apr/2015:12:34:56 +0000`.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn format_request_time(time: &DateTime<Utc>) -> String {
+    time.format("%d/%b/%Y:%H:%M:%S %z").to_string()
+}
+
+/// Splits the `Cookie` headers into the `cookies` field of payload format 2.0 events.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn take_cookies(headers: &mut HeaderMap) -> Option<Vec<String>> {
+    let cookies: Vec<String> = headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(String::from)
+        .collect();
+    headers.remove(COOKIE);
+    if cookies.is_empty() {
+        None
+    } else {
+        Some(cookies)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+    use chrono::TimeZone;
+    use http::Request;
+
+    #[test]
+    #[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+    fn generated_request_ids_are_unique() {
+        let defaults = EventDefaults::new();
+        let a = defaults.next_request_id();
+        let b = defaults.next_request_id();
+        assert_ne!(a, b);
+        assert_eq!(36, a.len());
+        assert_eq!(
+            "fixed",
+            EventDefaults::new().request_id("fixed").next_request_id()
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+    fn request_time_format() {
+        let time = Utc.with_ymd_and_hms(2015, 4, 9, 12, 34, 56).unwrap();
+        assert_eq!("09/Apr/2015:12:34:56 +0000", format_request_time(&time));
+    }
+
+    #[test]
+    fn add_forwarded_headers() {
+        let (parts, _) = Request::get("http://localhost:3000/hello")
+            .header("x-forwarded-for", "192.0.2.1")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let defaults = EventDefaults::new();
+        let host = defaults.host(&parts, "example.com");
+        assert_eq!("localhost:3000", host);
+
+        let headers = defaults.forwarded_headers(&parts, &host);
+        assert_eq!("localhost:3000", headers["host"]);
+        assert_eq!("192.0.2.1", headers["x-forwarded-for"]);
+        assert_eq!("http", headers["x-forwarded-proto"]);
+        assert_eq!("3000", headers["x-forwarded-port"]);
+    }
+
+    #[test]
+    fn encode_request_bodies() {
+        let defaults = EventDefaults::new();
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            (Some("hello".to_string()), false),
+            defaults.encode_body(&headers, Body::from("hello"))
+        );
+        assert_eq!((None, false), defaults.encode_body(&headers, Body::Empty));
+
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+        assert_eq!(
+            (Some("AJ+Slg==".to_string()), true),
+            defaults.encode_body(&headers, Body::from(vec![0, 159, 146, 150]))
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+    fn split_cookies() {
+        let mut headers = HeaderMap::new();
+        headers.append("cookie", HeaderValue::from_static("a=1; b=2"));
+        headers.append("cookie", HeaderValue::from_static("c=3"));
+        assert_eq!(
+            Some(vec![
+                "a=1".to_string(),
+                "b=2".to_string(),
+                "c=3".to_string()
+            ]),
+            take_cookies(&mut headers)
+        );
+        assert!(!headers.contains_key("cookie"));
+        assert_eq!(None, take_cookies(&mut headers));
+    }
+}
@@ -0,0 +1,220 @@
+use super::defaults::{domain_prefix, format_request_time, parse_query, take_cookies, user_agent};
+use super::{
+    build_uri, decode_body, encode_body_for_content_type, insert_cookies, join_headers,
+    take_set_cookies, ConversionError, EventDefaults,
+};
+use crate::encodings::{Body, BodyError};
+use crate::lambda_function_urls::{
+    LambdaFunctionUrlRequest, LambdaFunctionUrlRequestContext,
+    LambdaFunctionUrlRequestContextHttpDescription, LambdaFunctionUrlResponse,
+};
+use http::{Method, Request, Response};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+impl LambdaFunctionUrlRequest {
+    /// Returns the request body, decoded from base64 when `is_base64_encoded` is set.
+    pub fn decoded_body(&self) -> Result<Body, BodyError> {
+        decode_body(self.is_base64_encoded, self.body.as_deref())
+    }
+}
+
+impl LambdaFunctionUrlRequest {
+    /// Builds the event that a function URL without authentication sends for `req`.
+    ///
+    /// The request context is filled in from `defaults`, using the API id as the URL id.
+    /// `Cookie` headers are moved into `cookies`, and repeated headers and query parameters
+    /// are joined with commas.
+    pub fn from_http_request(req: Request<Body>, defaults: &EventDefaults) -> Self {
+        let (parts, body) = req.into_parts();
+        let domain_name = defaults.host(
+            &parts,
+            &format!("{}.lambda-url.{}.on.aws", defaults.api_id, defaults.region),
+        );
+        let mut headers = defaults.forwarded_headers(&parts, &domain_name);
+        let cookies = take_cookies(&mut headers);
+        let (body, is_base64_encoded) = defaults.encode_body(&headers, body);
+        let path = parts.uri.path().to_string();
+        let time = defaults.now();
+
+        let mut query_string_parameters: HashMap<String, String> = HashMap::new();
+        for (key, value) in parse_query(parts.uri.query()).iter() {
+            query_string_parameters
+                .entry(key.to_string())
+                .and_modify(|values| {
+                    values.push(',');
+                    values.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+
+        LambdaFunctionUrlRequest {
+            version: Some("2.0".to_string()),
+            raw_path: Some(path.clone()),
+            raw_query_string: Some(parts.uri.query().unwrap_or_default().to_string()),
+            cookies,
+            query_string_parameters,
+            request_context: LambdaFunctionUrlRequestContext {
+                account_id: Some("anonymous".to_string()),
+                request_id: Some(defaults.next_request_id()),
+                authorizer: None,
+                apiid: Some(defaults.api_id.clone()),
+                domain_prefix: Some(domain_prefix(&domain_name)),
+                domain_name: Some(domain_name),
+                time: Some(format_request_time(&time)),
+                time_epoch: time.timestamp_millis(),
+                http: LambdaFunctionUrlRequestContextHttpDescription {
+                    method: Some(parts.method.to_string()),
+                    path: Some(path),
+                    protocol: Some(format!("{:?}", parts.version)),
+                    source_ip: Some(defaults.source_ip.clone()),
+                    user_agent: user_agent(&headers),
+                },
+            },
+            headers: join_headers(headers),
+            body,
+            is_base64_encoded,
+        }
+    }
+}
+
+/// Converts a Lambda Function URL event into an `http::Request`.
+///
+/// The `Cookie` header is rebuilt from `cookies`, and the query is taken from `raw_query_string`.
+/// The `LambdaFunctionUrlRequestContext` is available in the request extensions.
+impl TryFrom<LambdaFunctionUrlRequest> for Request<Body> {
+    type Error = ConversionError;
+
+    fn try_from(event: LambdaFunctionUrlRequest) -> Result<Self, Self::Error> {
+        let body = event.decoded_body()?;
+        let mut headers = event.headers;
+        insert_cookies(&mut headers, event.cookies.as_deref())?;
+        let uri = build_uri(
+            &headers,
+            event.request_context.domain_name.as_deref(),
+            event.raw_path.as_deref(),
+            event.raw_query_string.as_deref().unwrap_or_default(),
+        );
+        let method = match event.request_context.http.method {
+            Some(ref method) => Method::from_bytes(method.as_bytes()).map_err(http::Error::from)?,
+            None => Method::GET,
+        };
+
+        let mut req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .extension(event.request_context)
+            .body(body)?;
+        *req.headers_mut() = headers;
+        Ok(req)
+    }
+}
+
+/// Converts an `http::Response` into the response expected by a Lambda Function URL.
+///
+/// `Set-Cookie` headers are moved into `cookies`, and the body is base64
+/// encoded unless the `Content-Type` header describes text.
+impl From<Response<Body>> for LambdaFunctionUrlResponse {
+    fn from(res: Response<Body>) -> Self {
+        let (mut parts, body) = res.into_parts();
+        let cookies = take_set_cookies(&mut parts.headers);
+        let (body, is_base64_encoded) = encode_body_for_content_type(&parts.headers, body);
+        let body = body.map(|body| match body {
+            Body::Empty => String::new(),
+            Body::Text(text) => text,
+            Body::Binary(bytes) => base64::encode(bytes),
+        });
+        LambdaFunctionUrlResponse {
+            status_code: parts.status.as_u16() as i64,
+            headers: join_headers(parts.headers),
+            body,
+            is_base64_encoded,
+            cookies,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lambda_function_urls::LambdaFunctionUrlRequestContext;
+    use http::header::{CONTENT_TYPE, COOKIE, SET_COOKIE};
+
+    extern crate serde_json;
+
+    #[test]
+    fn example_lambda_function_url_request_into_http_request() {
+        let data = include_bytes!("../fixtures/example-lambda-function-url-request.json");
+        let event: LambdaFunctionUrlRequest = serde_json::from_slice(data).unwrap();
+        let req = Request::try_from(event).unwrap();
+
+        assert_eq!(Method::POST, req.method());
+        assert_eq!(
+            "https://abcdefghijklmnopqrstuvwxyz.lambda-url.us-west-2.on.aws/my/path?parameter1=value1&parameter1=value2&parameter2=value",
+            req.uri().to_string()
+        );
+        assert_eq!("cookie1; cookie2", req.headers()[COOKIE]);
+        assert_eq!(&Body::from("Hello from client!".as_bytes()), req.body());
+
+        let context = req.extensions().get::<LambdaFunctionUrlRequestContext>();
+        assert_eq!(Some("id"), context.unwrap().request_id.as_deref());
+    }
+
+    #[test]
+    fn http_response_into_lambda_function_url_response() {
+        let res = Response::builder()
+            .status(302)
+            .header(SET_COOKIE, "a=1")
+            .header(SET_COOKIE, "b=2")
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .body(Body::from("bin".as_bytes()))
+            .unwrap();
+        let res = LambdaFunctionUrlResponse::from(res);
+
+        assert_eq!(302, res.status_code);
+        assert_eq!(vec!["a=1", "b=2"], res.cookies);
+        assert!(!res.headers.contains_key(SET_COOKIE));
+        assert_eq!(Some("Ymlu"), res.body.as_deref());
+        assert!(res.is_base64_encoded);
+    }
+
+    #[test]
+    fn function_url_request_from_http_request() {
+        let req = Request::post("/my/path?tags=a&tags=b")
+            .header(COOKIE, "session=1")
+            .header("user-agent", "test")
+            .body(Body::from("hello"))
+            .unwrap();
+        let defaults = EventDefaults::new().api_id("abcdefg").region("eu-west-1");
+        let event = LambdaFunctionUrlRequest::from_http_request(req, &defaults);
+
+        assert_eq!(Some("/my/path"), event.raw_path.as_deref());
+        assert_eq!(
+            Some("a,b"),
+            event
+                .query_string_parameters
+                .get("tags")
+                .map(String::as_str)
+        );
+        assert_eq!(Some(vec!["session=1".to_string()]), event.cookies);
+        assert_eq!(Some("hello"), event.body.as_deref());
+        assert!(!event.is_base64_encoded);
+
+        let context = &event.request_context;
+        assert_eq!(Some("anonymous"), context.account_id.as_deref());
+        assert_eq!(
+            Some("abcdefg.lambda-url.eu-west-1.on.aws"),
+            context.domain_name.as_deref()
+        );
+        assert_eq!(Some("POST"), context.http.method.as_deref());
+        assert_eq!(Some("test"), context.http.user_agent.as_deref());
+        assert!(context.request_id.is_some());
+
+        let req = Request::try_from(event).unwrap();
+        assert_eq!(
+            "https://abcdefg.lambda-url.eu-west-1.on.aws/my/path?tags=a&tags=b",
+            req.uri().to_string()
+        );
+        assert_eq!("session=1", req.headers()[COOKIE]);
+    }
+}
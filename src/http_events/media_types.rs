@@ -0,0 +1,120 @@
+use crate::encodings::Body;
+
+/// `BinaryMediaTypes` decides which response bodies are returned base64 encoded,
+/// like the `binaryMediaTypes` setting of an API Gateway REST API.
+///
+/// The default list treats every media type that doesn't describe text as binary.
+/// Bodies that are not valid UTF-8 are always base64 encoded, since they can't be
+/// returned as a JSON string.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BinaryMediaTypes {
+    media_types: Option<Vec<String>>,
+}
+
+impl BinaryMediaTypes {
+    /// Creates a list of binary media types, e.g. `["image/png", "application/*"]`.
+    ///
+    /// `*/*` treats every media type as binary.
+    pub fn new<I, S>(media_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        BinaryMediaTypes {
+            media_types: Some(
+                media_types
+                    .into_iter()
+                    .map(|m| m.into().to_ascii_lowercase())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns whether a body with the given `Content-Type` is returned base64 encoded.
+    pub fn is_binary(&self, content_type: &str) -> bool {
+        let essence = essence(content_type);
+        match self.media_types {
+            None => !is_text(&essence),
+            Some(ref media_types) => media_types.iter().any(|m| media_type_matches(m, &essence)),
+        }
+    }
+
+    /// Splits a response body into the `body` and `is_base64_encoded` fields of a response event.
+    pub(crate) fn encode(&self, content_type: &str, body: Body) -> (Option<Body>, bool) {
+        let bytes = match body {
+            Body::Empty => return (None, false),
+            Body::Text(text) => text.into_bytes(),
+            Body::Binary(bytes) => bytes,
+        };
+        if self.is_binary(content_type) {
+            return (Some(Body::Binary(bytes)), true);
+        }
+        match String::from_utf8(bytes) {
+            Ok(text) => (Some(Body::Text(text)), false),
+            Err(e) => (Some(Body::Binary(e.into_bytes())), true),
+        }
+    }
+}
+
+fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn media_type_matches(pattern: &str, essence: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some("*") => true,
+        Some(prefix) => essence.split('/').next() == Some(prefix),
+        None => pattern == essence,
+    }
+}
+
+fn is_text(essence: &str) -> bool {
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence,
+            "application/json"
+                | "application/javascript"
+                | "application/xml"
+                | "application/x-www-form-urlencoded"
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_binary_media_types() {
+        let media_types = BinaryMediaTypes::default();
+        assert!(!media_types.is_binary("application/json; charset=utf-8"));
+        assert!(!media_types.is_binary("text/html"));
+        assert!(!media_types.is_binary("image/svg+xml"));
+        assert!(media_types.is_binary("image/png"));
+        assert!(media_types.is_binary("application/octet-stream"));
+    }
+
+    #[test]
+    fn configured_binary_media_types() {
+        let media_types = BinaryMediaTypes::new(vec!["image/*", "application/pdf"]);
+        assert!(media_types.is_binary("image/png"));
+        assert!(media_types.is_binary("Application/PDF"));
+        assert!(!media_types.is_binary("application/octet-stream"));
+        assert!(!media_types.is_binary("imagery/png"));
+        assert!(BinaryMediaTypes::new(vec!["*/*"]).is_binary("text/plain"));
+
+        let (body, is_base64_encoded) = media_types.encode("font/woff2", Body::from("font"));
+        assert_eq!(Some(Body::from("font")), body);
+        assert!(!is_base64_encoded);
+
+        let (body, is_base64_encoded) = media_types.encode("font/woff2", Body::from(vec![0xff]));
+        assert_eq!(Some(Body::from(vec![0xff])), body);
+        assert!(is_base64_encoded);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::encodings::{Body, BodyError};
+use http::header::{HeaderName, HeaderValue, HOST};
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+use http::header::{CONTENT_TYPE, COOKIE, SET_COOKIE};
+use http::HeaderMap;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use std::{error, fmt};
+
+#[cfg(feature = "alb")]
+mod alb;
+#[cfg(feature = "apigw")]
+mod apigw;
+#[cfg(feature = "compression")]
+mod compression;
+mod cors;
+mod defaults;
+#[cfg(feature = "lambda_function_urls")]
+mod lambda_function_urls;
+mod media_types;
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+mod request;
+mod response;
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+mod router;
+#[cfg(feature = "tower")]
+mod service;
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+pub use self::request::*;
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+pub use self::router::{RouteRequest, Router};
+
+#[cfg(feature = "compression")]
+pub use self::compression::{Compression, ContentEncoding};
+pub use self::cors::Cors;
+pub use self::defaults::EventDefaults;
+pub use self::media_types::BinaryMediaTypes;
+pub use self::response::HttpResponseEvent;
+#[cfg(feature = "tower")]
+pub use self::service::{
+    EventService, EventServiceError, EventServiceFuture, HttpRequestEvent, ResponseBuilder,
+};
+
+/// Characters that are not allowed to appear verbatim in the path of a URI.
+const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters that are not allowed to appear verbatim in the query of a URI.
+///
+/// `%` is not part of the set, so values that are already percent-encoded are kept as they are.
+#[cfg(feature = "alb")]
+pub(crate) const QUERY_ENCODE_SET: &AsciiSet =
+    &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>');
+
+/// `ConversionError` is returned when a Lambda HTTP event can not be turned into an `http::Request`.
+#[derive(Debug)]
+pub enum ConversionError {
+    /// The event body could not be decoded.
+    InvalidBody(BodyError),
+    /// The method, URI or headers in the event do not form a valid `http::Request`.
+    Http(http::Error),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::InvalidBody(e) => write!(f, "invalid request body: {}", e),
+            ConversionError::Http(e) => write!(f, "invalid http request: {}", e),
+        }
+    }
+}
+
+impl error::Error for ConversionError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ConversionError::InvalidBody(e) => Some(e),
+            ConversionError::Http(e) => Some(e),
+        }
+    }
+}
+
+impl From<BodyError> for ConversionError {
+    fn from(e: BodyError) -> Self {
+        ConversionError::InvalidBody(e)
+    }
+}
+
+impl From<http::Error> for ConversionError {
+    fn from(e: http::Error) -> Self {
+        ConversionError::Http(e)
+    }
+}
+
+/// Merges the single and multi value header maps sent by AWS.
+///
+/// Values in `multi_value_headers` win, since they are a superset of the
+/// single value ones when both maps are present.
+#[cfg(any(feature = "alb", feature = "apigw"))]
+pub(crate) fn merge_headers(headers: &HeaderMap, multi_value_headers: &HeaderMap) -> HeaderMap {
+    let mut merged = multi_value_headers.clone();
+    for (name, value) in headers {
+        if !merged.contains_key(name) {
+            merged.insert(name, value.clone());
+        }
+    }
+    merged
+}
+
+/// Builds the request URI from the event path and query.
+///
+/// The URI is absolute when the host is known, either from the `Host` header
+/// or from the domain name in the request context.
+pub(crate) fn build_uri(
+    headers: &HeaderMap,
+    domain_name: Option<&str>,
+    path: Option<&str>,
+    query: &str,
+) -> String {
+    let path = utf8_percent_encode(path.unwrap_or("/"), PATH_ENCODE_SET).to_string();
+    let mut uri = match header_str(headers, &HOST).or(domain_name) {
+        Some(host) => {
+            let scheme = header_str(headers, &HeaderName::from_static("x-forwarded-proto"))
+                .unwrap_or("https");
+            format!("{}://{}{}", scheme, host, path)
+        }
+        None => path,
+    };
+    if !query.is_empty() {
+        uri.push('?');
+        uri.push_str(query);
+    }
+    uri
+}
+
+/// Decodes the event body, if needed.
+pub(crate) fn decode_body(is_base64_encoded: bool, body: Option<&str>) -> Result<Body, BodyError> {
+    match body {
+        None => Ok(Body::Empty),
+        Some(body) => Body::try_from_maybe_encoded(is_base64_encoded, body),
+    }
+}
+
+/// Splits a response body into the `body` and `is_base64_encoded` fields of a response event.
+pub(crate) fn encode_body(body: Body) -> (Option<Body>, bool) {
+    match body {
+        Body::Empty => (None, false),
+        b @ Body::Text(_) => (Some(b), false),
+        b @ Body::Binary(_) => (Some(b), true),
+    }
+}
+
+/// Splits a response body into the `body` and `is_base64_encoded` fields of a response event,
+/// using the `Content-Type` header to decide whether the body can be sent as text.
+///
+/// Bodies without a `Content-Type` header keep the encoding implied by their `Body` variant.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn encode_body_for_content_type(
+    headers: &HeaderMap,
+    body: Body,
+) -> (Option<Body>, bool) {
+    match header_str(headers, &CONTENT_TYPE) {
+        Some(content_type) => BinaryMediaTypes::default().encode(content_type, body),
+        None => encode_body(body),
+    }
+}
+
+/// Adds a `Cookie` header rebuilt from the `cookies` field of a request event.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn insert_cookies(
+    headers: &mut HeaderMap,
+    cookies: Option<&[String]>,
+) -> Result<(), http::Error> {
+    match cookies {
+        Some(cookies) if !cookies.is_empty() => {
+            let value = HeaderValue::from_str(&cookies.join("; "))?;
+            headers.insert(COOKIE, value);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Removes the `Set-Cookie` headers from a response, returning their values
+/// for the `cookies` field of a response event.
+#[cfg(any(feature = "apigw", feature = "lambda_function_urls"))]
+pub(crate) fn take_set_cookies(headers: &mut HeaderMap) -> Vec<String> {
+    let cookies = headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .map(String::from)
+        .collect();
+    headers.remove(SET_COOKIE);
+    cookies
+}
+
+/// Joins repeated headers into a single comma separated value, for
+/// integrations that only accept one value per header.
+pub(crate) fn join_headers(headers: HeaderMap) -> HeaderMap {
+    let mut joined = HeaderMap::with_capacity(headers.keys_len());
+    for name in headers.keys() {
+        let values: Vec<&[u8]> = headers.get_all(name).iter().map(|v| v.as_bytes()).collect();
+        if let Ok(value) = HeaderValue::from_bytes(&values.join(&b", "[..])) {
+            joined.insert(name, value);
+        }
+    }
+    joined
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
@@ -0,0 +1,366 @@
+use super::alb::raw_query_string;
+use super::defaults::parse_query;
+use super::{decode_body, insert_cookies, merge_headers, ConversionError};
+use crate::alb::{AlbTargetGroupRequest, AlbTargetGroupResponse};
+use crate::apigw::{
+    ApiGatewayProxyRequest, ApiGatewayProxyResponse, ApiGatewayV2httpRequest,
+    ApiGatewayV2httpResponse, ApiGatewayWebsocketProxyRequest,
+};
+use crate::encodings::{Body, BodyError};
+use crate::lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse};
+use http::header::HeaderName;
+use http::{HeaderMap, Method, Request, Response};
+use query_map::QueryMap;
+use serde::de::{Deserialize, Deserializer, Error as DeError};
+use serde_json::Value;
+use std::convert::TryFrom;
+
+/// `LambdaHttpRequest` is any of the HTTP events that can invoke a Lambda function.
+///
+/// Deserializing a `LambdaHttpRequest` inspects the payload to choose the right variant:
+/// * `requestContext.elb` identifies an ALB target group event
+/// * `requestContext.connectionId` identifies a WebSocket API event
+/// * `requestContext.http` or `"version": "2.0"` identify an HTTP API event, or a
+///   Lambda Function URL event when the domain name is a `lambda-url` domain
+/// * anything else is treated as a REST API event
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum LambdaHttpRequest {
+    /// An API Gateway REST API event (payload format 1.0)
+    ApiGatewayV1(ApiGatewayProxyRequest),
+    /// An API Gateway HTTP API event (payload format 2.0)
+    ApiGatewayV2(ApiGatewayV2httpRequest),
+    /// An API Gateway WebSocket API event
+    WebSocket(ApiGatewayWebsocketProxyRequest),
+    /// An ALB target group event
+    Alb(AlbTargetGroupRequest),
+    /// A Lambda Function URL event
+    FunctionUrl(LambdaFunctionUrlRequest),
+}
+
+/// `LambdaHttpResponse` is the response expected by the integration that sent a `LambdaHttpRequest`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum LambdaHttpResponse {
+    /// A response for REST and WebSocket API events
+    ApiGatewayV1(ApiGatewayProxyResponse),
+    /// A response for HTTP API events
+    ApiGatewayV2(ApiGatewayV2httpResponse),
+    /// A response for ALB target group events
+    Alb(AlbTargetGroupResponse),
+    /// A response for Lambda Function URL events
+    FunctionUrl(LambdaFunctionUrlResponse),
+}
+
+impl LambdaHttpRequest {
+    /// The HTTP method of the request. WebSocket events without a method are reported as `GET`.
+    pub fn method(&self) -> Method {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(r) => r.http_method.clone(),
+            LambdaHttpRequest::ApiGatewayV2(r) => r.request_context.http.method.clone(),
+            LambdaHttpRequest::WebSocket(r) => r.http_method.clone().unwrap_or(Method::GET),
+            LambdaHttpRequest::Alb(r) => r.http_method.clone(),
+            LambdaHttpRequest::FunctionUrl(r) => r
+                .request_context
+                .http
+                .method
+                .as_ref()
+                .and_then(|m| Method::from_bytes(m.as_bytes()).ok())
+                .unwrap_or(Method::GET),
+        }
+    }
+
+    /// The path requested by the caller.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(r) => r.path.as_deref(),
+            LambdaHttpRequest::ApiGatewayV2(r) => r.raw_path.as_deref(),
+            LambdaHttpRequest::WebSocket(r) => r.path.as_deref(),
+            LambdaHttpRequest::Alb(r) => r.path.as_deref(),
+            LambdaHttpRequest::FunctionUrl(r) => r.raw_path.as_deref(),
+        }
+    }
+
+    /// The request headers, with single and multi value headers merged and
+    /// the `Cookie` header rebuilt for events that send cookies separately.
+    pub fn headers(&self) -> HeaderMap {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(r) => merge_headers(&r.headers, &r.multi_value_headers),
+            LambdaHttpRequest::ApiGatewayV2(r) => {
+                let mut headers = r.headers.clone();
+                let _ = insert_cookies(&mut headers, r.cookies.as_deref());
+                headers
+            }
+            LambdaHttpRequest::WebSocket(r) => merge_headers(&r.headers, &r.multi_value_headers),
+            LambdaHttpRequest::Alb(r) => merge_headers(&r.headers, &r.multi_value_headers),
+            LambdaHttpRequest::FunctionUrl(r) => {
+                let mut headers = r.headers.clone();
+                let _ = insert_cookies(&mut headers, r.cookies.as_deref());
+                headers
+            }
+        }
+    }
+
+    /// The decoded query string parameters, including repeated parameters.
+    pub fn query(&self) -> QueryMap {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(r) => {
+                if r.multi_value_query_string_parameters.is_empty() {
+                    r.query_string_parameters.clone()
+                } else {
+                    r.multi_value_query_string_parameters.clone()
+                }
+            }
+            LambdaHttpRequest::ApiGatewayV2(r) => parse_query(r.raw_query_string.as_deref()),
+            LambdaHttpRequest::WebSocket(r) => {
+                if r.multi_value_query_string_parameters.is_empty() {
+                    r.query_string_parameters.clone()
+                } else {
+                    r.multi_value_query_string_parameters.clone()
+                }
+            }
+            LambdaHttpRequest::Alb(r) => {
+                let query = if r.multi_value_query_string_parameters.is_empty() {
+                    raw_query_string(&r.query_string_parameters)
+                } else {
+                    raw_query_string(&r.multi_value_query_string_parameters)
+                };
+                parse_query(Some(&query))
+            }
+            LambdaHttpRequest::FunctionUrl(r) => parse_query(r.raw_query_string.as_deref()),
+        }
+    }
+
+    /// The request body, decoded from base64 if needed.
+    pub fn body(&self) -> Result<Body, BodyError> {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(r) => r.decoded_body(),
+            LambdaHttpRequest::ApiGatewayV2(r) => r.decoded_body(),
+            LambdaHttpRequest::WebSocket(r) => {
+                decode_body(r.is_base64_encoded.unwrap_or_default(), r.body.as_deref())
+            }
+            LambdaHttpRequest::Alb(r) => r.decoded_body(),
+            LambdaHttpRequest::FunctionUrl(r) => r.decoded_body(),
+        }
+    }
+
+    /// The IP address of the caller.
+    ///
+    /// ALB events don't include it in the request context, so it's taken
+    /// from the first address in the `X-Forwarded-For` header.
+    pub fn source_ip(&self) -> Option<&str> {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(r) => r.request_context.identity.source_ip.as_deref(),
+            LambdaHttpRequest::ApiGatewayV2(r) => r.request_context.http.source_ip.as_deref(),
+            LambdaHttpRequest::WebSocket(r) => r.request_context.identity.source_ip.as_deref(),
+            LambdaHttpRequest::Alb(r) => {
+                let name = HeaderName::from_static("x-forwarded-for");
+                r.headers
+                    .get(&name)
+                    .or_else(|| r.multi_value_headers.get(&name))
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.split(',').next())
+                    .map(str::trim)
+            }
+            LambdaHttpRequest::FunctionUrl(r) => r.request_context.http.source_ip.as_deref(),
+        }
+    }
+
+    /// The id that AWS assigned to the request. ALB events don't have one.
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(r) => r.request_context.request_id.as_deref(),
+            LambdaHttpRequest::ApiGatewayV2(r) => r.request_context.request_id.as_deref(),
+            LambdaHttpRequest::WebSocket(r) => r.request_context.request_id.as_deref(),
+            LambdaHttpRequest::Alb(_) => None,
+            LambdaHttpRequest::FunctionUrl(r) => r.request_context.request_id.as_deref(),
+        }
+    }
+
+    /// Converts an `http::Response` into the response type expected by the
+    /// integration that sent this request.
+    ///
+    /// ALB responses use multi value headers when the request had them.
+    pub fn build_response(&self, res: Response<Body>) -> LambdaHttpResponse {
+        self.response_kind().build_response(res)
+    }
+
+    pub(crate) fn response_kind(&self) -> ResponseKind {
+        match self {
+            LambdaHttpRequest::ApiGatewayV1(_) | LambdaHttpRequest::WebSocket(_) => {
+                ResponseKind::ApiGatewayV1
+            }
+            LambdaHttpRequest::ApiGatewayV2(_) => ResponseKind::ApiGatewayV2,
+            LambdaHttpRequest::Alb(r) => ResponseKind::Alb {
+                multi_value_headers: !r.multi_value_headers.is_empty(),
+            },
+            LambdaHttpRequest::FunctionUrl(_) => ResponseKind::FunctionUrl,
+        }
+    }
+}
+
+/// The response type expected by the integration that sent a `LambdaHttpRequest`,
+/// kept so the response can be built after the request was consumed.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ResponseKind {
+    ApiGatewayV1,
+    ApiGatewayV2,
+    Alb { multi_value_headers: bool },
+    FunctionUrl,
+}
+
+impl ResponseKind {
+    pub(crate) fn build_response(self, res: Response<Body>) -> LambdaHttpResponse {
+        match self {
+            ResponseKind::ApiGatewayV1 => LambdaHttpResponse::ApiGatewayV1(res.into()),
+            ResponseKind::ApiGatewayV2 => LambdaHttpResponse::ApiGatewayV2(res.into()),
+            ResponseKind::Alb {
+                multi_value_headers,
+            } => LambdaHttpResponse::Alb(AlbTargetGroupResponse::from_http_response(
+                res,
+                multi_value_headers,
+            )),
+            ResponseKind::FunctionUrl => LambdaHttpResponse::FunctionUrl(res.into()),
+        }
+    }
+}
+
+impl TryFrom<LambdaHttpRequest> for Request<Body> {
+    type Error = ConversionError;
+
+    fn try_from(event: LambdaHttpRequest) -> Result<Self, Self::Error> {
+        match event {
+            LambdaHttpRequest::ApiGatewayV1(r) => Request::try_from(r),
+            LambdaHttpRequest::ApiGatewayV2(r) => Request::try_from(r),
+            LambdaHttpRequest::WebSocket(r) => Request::try_from(r),
+            LambdaHttpRequest::Alb(r) => Request::try_from(r),
+            LambdaHttpRequest::FunctionUrl(r) => Request::try_from(r),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LambdaHttpRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        let context = value.get("requestContext");
+        let has_context_field = |field: &str| context.and_then(|c| c.get(field)).is_some();
+
+        let event = if has_context_field("elb") {
+            serde_json::from_value(value).map(LambdaHttpRequest::Alb)
+        } else if has_context_field("connectionId") {
+            serde_json::from_value(value).map(LambdaHttpRequest::WebSocket)
+        } else if has_context_field("http")
+            || value.get("version").and_then(Value::as_str) == Some("2.0")
+        {
+            let is_function_url = match context
+                .and_then(|c| c.get("domainName"))
+                .and_then(Value::as_str)
+            {
+                Some(domain) => domain.contains(".lambda-url."),
+                None => false,
+            };
+            if is_function_url {
+                serde_json::from_value(value).map(LambdaHttpRequest::FunctionUrl)
+            } else {
+                serde_json::from_value(value).map(LambdaHttpRequest::ApiGatewayV2)
+            }
+        } else {
+            serde_json::from_value(value).map(LambdaHttpRequest::ApiGatewayV1)
+        };
+        event.map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate serde_json;
+
+    #[test]
+    fn example_lambda_http_request_variants() {
+        let data = include_bytes!("../fixtures/example-apigw-request.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert!(matches!(parsed, LambdaHttpRequest::ApiGatewayV1(_)));
+
+        let data = include_bytes!("../fixtures/example-apigw-v2-request-iam.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert!(matches!(parsed, LambdaHttpRequest::ApiGatewayV2(_)));
+
+        let data =
+            include_bytes!("../fixtures/example-apigw-websocket-request-without-method.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert!(matches!(parsed, LambdaHttpRequest::WebSocket(_)));
+
+        let data =
+            include_bytes!("../fixtures/example-alb-lambda-target-request-headers-only.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert!(matches!(parsed, LambdaHttpRequest::Alb(_)));
+
+        let data = include_bytes!("../fixtures/example-lambda-function-url-request.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert!(matches!(parsed, LambdaHttpRequest::FunctionUrl(_)));
+    }
+
+    #[test]
+    fn lambda_http_request_accessors() {
+        let data = include_bytes!("../fixtures/example-apigw-request.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert_eq!(Method::POST, parsed.method());
+        assert_eq!(Some("/hello/world"), parsed.path());
+        assert_eq!("application/json", parsed.headers()["content-type"]);
+        assert_eq!(Some("me"), parsed.query().first("name"));
+        assert_eq!(Body::from("{\r\n\t\"a\": 1\r\n}"), parsed.body().unwrap());
+        assert_eq!(Some("192.168.196.186"), parsed.source_ip());
+        assert_eq!(
+            Some("deef4878-7910-11e6-8f14-25afc3e9ae33"),
+            parsed.request_id()
+        );
+
+        let data =
+            include_bytes!("../fixtures/example-alb-lambda-target-request-multivalue-headers.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert_eq!(Some("72.21.198.67"), parsed.source_ip());
+        assert_eq!(Some("hello"), parsed.query().first("key"));
+        assert_eq!(None, parsed.request_id());
+
+        let data = include_bytes!("../fixtures/example-lambda-function-url-request.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        assert_eq!(
+            Some(vec!["value1", "value2"]),
+            parsed.query().all("parameter1")
+        );
+        assert_eq!("cookie1; cookie2", parsed.headers()["cookie"]);
+    }
+
+    #[test]
+    fn lambda_http_request_builds_matching_response() {
+        let data = include_bytes!("../fixtures/example-apigw-v2-request-iam.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        let res = parsed.build_response(Response::new(Body::from("ok")));
+        assert!(matches!(res, LambdaHttpResponse::ApiGatewayV2(_)));
+
+        let data =
+            include_bytes!("../fixtures/example-alb-lambda-target-request-multivalue-headers.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        let res = Response::builder()
+            .header("x-custom", "value")
+            .body(Body::from("ok"))
+            .unwrap();
+        match parsed.build_response(res) {
+            LambdaHttpResponse::Alb(res) => {
+                assert!(res.headers.is_empty());
+                assert_eq!("value", res.multi_value_headers["x-custom"]);
+            }
+            not => panic!("expected LambdaHttpResponse::Alb(...) got {:?}", not),
+        }
+
+        let data = include_bytes!("../fixtures/example-apigw-websocket-request.json");
+        let parsed: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        let res = parsed.build_response(Response::new(Body::Empty));
+        assert!(matches!(res, LambdaHttpResponse::ApiGatewayV1(_)));
+    }
+}
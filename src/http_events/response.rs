@@ -0,0 +1,300 @@
+use super::decode_body;
+use crate::encodings::{Body, BodyError};
+use http::header::{HeaderName, HeaderValue, VARY};
+use http::Response;
+
+#[cfg(any(feature = "alb", feature = "apigw"))]
+use super::encode_body;
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+use super::LambdaHttpResponse;
+#[cfg(feature = "alb")]
+use crate::alb::AlbTargetGroupResponse;
+#[cfg(feature = "apigw")]
+use crate::apigw::{ApiGatewayProxyResponse, ApiGatewayV2httpResponse};
+#[cfg(feature = "lambda_function_urls")]
+use crate::lambda_function_urls::LambdaFunctionUrlResponse;
+#[cfg(any(feature = "alb", feature = "apigw"))]
+use http::HeaderMap;
+
+/// `HttpResponseEvent` gives uniform access to the headers and body of the HTTP response events,
+/// so they can be post-processed after a handler built them.
+///
+/// Responses with both `headers` and `multi_value_headers` are read from both maps,
+/// and headers are written to every map that is in use.
+pub trait HttpResponseEvent {
+    /// Returns the first value of a response header.
+    fn header(&self, name: &HeaderName) -> Option<&HeaderValue>;
+
+    /// Sets a response header, replacing any previous values.
+    fn set_header(&mut self, name: HeaderName, value: HeaderValue);
+
+    /// Removes every value of a response header.
+    fn remove_header(&mut self, name: &HeaderName);
+
+    /// Returns the response body, decoded from base64 when `is_base64_encoded` is set.
+    fn decoded_body(&self) -> Result<Body, BodyError>;
+
+    /// Replaces the response body, setting `is_base64_encoded` for `Body::Binary` bodies.
+    fn set_body(&mut self, body: Body);
+}
+
+/// Adds `name` to the `Vary` header of a response, unless it's already listed.
+pub(crate) fn add_vary<R: HttpResponseEvent>(response: &mut R, name: &str) {
+    let vary = match response.header(&VARY).and_then(|v| v.to_str().ok()) {
+        Some(vary)
+            if vary
+                .split(',')
+                .map(str::trim)
+                .any(|v| v == "*" || v.eq_ignore_ascii_case(name)) =>
+        {
+            return
+        }
+        Some(vary) => HeaderValue::from_str(&format!("{}, {}", vary, name)).ok(),
+        None => HeaderValue::from_str(name).ok(),
+    };
+    if let Some(vary) = vary {
+        response.set_header(VARY, vary);
+    }
+}
+
+#[cfg(any(feature = "alb", feature = "apigw"))]
+fn header<'a>(
+    headers: &'a HeaderMap,
+    multi_value_headers: &'a HeaderMap,
+    name: &HeaderName,
+) -> Option<&'a HeaderValue> {
+    headers.get(name).or_else(|| multi_value_headers.get(name))
+}
+
+#[cfg(any(feature = "alb", feature = "apigw"))]
+fn set_header(
+    headers: &mut HeaderMap,
+    multi_value_headers: &mut HeaderMap,
+    name: HeaderName,
+    value: HeaderValue,
+) {
+    if !multi_value_headers.is_empty() {
+        multi_value_headers.insert(name.clone(), value.clone());
+    }
+    if !headers.is_empty() || multi_value_headers.is_empty() {
+        headers.insert(name, value);
+    }
+}
+
+#[cfg(any(feature = "alb", feature = "apigw"))]
+fn decoded_body(is_base64_encoded: bool, body: Option<&Body>) -> Result<Body, BodyError> {
+    match body {
+        Some(Body::Text(text)) => decode_body(is_base64_encoded, Some(text)),
+        Some(body) => Ok(body.clone()),
+        None => Ok(Body::Empty),
+    }
+}
+
+/// The `is_base64_encoded` field is optional in some response events.
+#[cfg(any(feature = "alb", feature = "apigw"))]
+trait Base64Flag {
+    fn is_set(&self) -> bool;
+    fn from_flag(is_base64_encoded: bool) -> Self;
+}
+
+#[cfg(any(feature = "alb", feature = "apigw"))]
+impl Base64Flag for bool {
+    fn is_set(&self) -> bool {
+        *self
+    }
+
+    fn from_flag(is_base64_encoded: bool) -> Self {
+        is_base64_encoded
+    }
+}
+
+#[cfg(any(feature = "alb", feature = "apigw"))]
+impl Base64Flag for Option<bool> {
+    fn is_set(&self) -> bool {
+        self.unwrap_or_default()
+    }
+
+    fn from_flag(is_base64_encoded: bool) -> Self {
+        Some(is_base64_encoded)
+    }
+}
+
+#[cfg(any(feature = "alb", feature = "apigw"))]
+macro_rules! impl_multi_value_response {
+    ($ty:ty) => {
+        impl HttpResponseEvent for $ty {
+            fn header(&self, name: &HeaderName) -> Option<&HeaderValue> {
+                header(&self.headers, &self.multi_value_headers, name)
+            }
+
+            fn set_header(&mut self, name: HeaderName, value: HeaderValue) {
+                set_header(
+                    &mut self.headers,
+                    &mut self.multi_value_headers,
+                    name,
+                    value,
+                )
+            }
+
+            fn remove_header(&mut self, name: &HeaderName) {
+                self.headers.remove(name);
+                self.multi_value_headers.remove(name);
+            }
+
+            fn decoded_body(&self) -> Result<Body, BodyError> {
+                decoded_body(self.is_base64_encoded.is_set(), self.body.as_ref())
+            }
+
+            fn set_body(&mut self, body: Body) {
+                let (body, is_base64_encoded) = encode_body(body);
+                self.body = body;
+                self.is_base64_encoded = Base64Flag::from_flag(is_base64_encoded);
+            }
+        }
+    };
+}
+
+#[cfg(feature = "apigw")]
+impl_multi_value_response!(ApiGatewayProxyResponse);
+#[cfg(feature = "apigw")]
+impl_multi_value_response!(ApiGatewayV2httpResponse);
+#[cfg(feature = "alb")]
+impl_multi_value_response!(AlbTargetGroupResponse);
+
+#[cfg(feature = "lambda_function_urls")]
+impl HttpResponseEvent for LambdaFunctionUrlResponse {
+    fn header(&self, name: &HeaderName) -> Option<&HeaderValue> {
+        self.headers.get(name)
+    }
+
+    fn set_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.headers.insert(name, value);
+    }
+
+    fn remove_header(&mut self, name: &HeaderName) {
+        self.headers.remove(name);
+    }
+
+    fn decoded_body(&self) -> Result<Body, BodyError> {
+        decode_body(self.is_base64_encoded, self.body.as_deref())
+    }
+
+    fn set_body(&mut self, body: Body) {
+        let (body, is_base64_encoded) = match body {
+            Body::Empty => (None, false),
+            Body::Text(text) => (Some(text), false),
+            Body::Binary(bytes) => (Some(base64::encode(bytes)), true),
+        };
+        self.body = body;
+        self.is_base64_encoded = is_base64_encoded;
+    }
+}
+
+impl HttpResponseEvent for Response<Body> {
+    fn header(&self, name: &HeaderName) -> Option<&HeaderValue> {
+        self.headers().get(name)
+    }
+
+    fn set_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.headers_mut().insert(name, value);
+    }
+
+    fn remove_header(&mut self, name: &HeaderName) {
+        self.headers_mut().remove(name);
+    }
+
+    fn decoded_body(&self) -> Result<Body, BodyError> {
+        Ok(self.body().clone())
+    }
+
+    fn set_body(&mut self, body: Body) {
+        *self.body_mut() = body;
+    }
+}
+
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+impl HttpResponseEvent for LambdaHttpResponse {
+    fn header(&self, name: &HeaderName) -> Option<&HeaderValue> {
+        match self {
+            LambdaHttpResponse::ApiGatewayV1(res) => res.header(name),
+            LambdaHttpResponse::ApiGatewayV2(res) => res.header(name),
+            LambdaHttpResponse::Alb(res) => res.header(name),
+            LambdaHttpResponse::FunctionUrl(res) => res.header(name),
+        }
+    }
+
+    fn set_header(&mut self, name: HeaderName, value: HeaderValue) {
+        match self {
+            LambdaHttpResponse::ApiGatewayV1(res) => res.set_header(name, value),
+            LambdaHttpResponse::ApiGatewayV2(res) => res.set_header(name, value),
+            LambdaHttpResponse::Alb(res) => res.set_header(name, value),
+            LambdaHttpResponse::FunctionUrl(res) => res.set_header(name, value),
+        }
+    }
+
+    fn remove_header(&mut self, name: &HeaderName) {
+        match self {
+            LambdaHttpResponse::ApiGatewayV1(res) => res.remove_header(name),
+            LambdaHttpResponse::ApiGatewayV2(res) => res.remove_header(name),
+            LambdaHttpResponse::Alb(res) => res.remove_header(name),
+            LambdaHttpResponse::FunctionUrl(res) => res.remove_header(name),
+        }
+    }
+
+    fn decoded_body(&self) -> Result<Body, BodyError> {
+        match self {
+            LambdaHttpResponse::ApiGatewayV1(res) => res.decoded_body(),
+            LambdaHttpResponse::ApiGatewayV2(res) => res.decoded_body(),
+            LambdaHttpResponse::Alb(res) => res.decoded_body(),
+            LambdaHttpResponse::FunctionUrl(res) => res.decoded_body(),
+        }
+    }
+
+    fn set_body(&mut self, body: Body) {
+        match self {
+            LambdaHttpResponse::ApiGatewayV1(res) => res.set_body(body),
+            LambdaHttpResponse::ApiGatewayV2(res) => res.set_body(body),
+            LambdaHttpResponse::Alb(res) => res.set_body(body),
+            LambdaHttpResponse::FunctionUrl(res) => res.set_body(body),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alb"))]
+mod test {
+    use super::*;
+    use http::header::{CONTENT_TYPE, VARY};
+
+    #[test]
+    fn set_header_writes_every_map_in_use() {
+        let mut res = AlbTargetGroupResponse::default();
+        res.set_header(VARY, HeaderValue::from_static("Origin"));
+        assert_eq!("Origin", res.headers[VARY]);
+        assert!(res.multi_value_headers.is_empty());
+
+        let mut res = AlbTargetGroupResponse::default();
+        res.multi_value_headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        res.set_header(VARY, HeaderValue::from_static("Origin"));
+        assert!(res.headers.is_empty());
+        assert_eq!(Some(&HeaderValue::from_static("Origin")), res.header(&VARY));
+        assert_eq!("text/plain", res.header(&CONTENT_TYPE).unwrap());
+
+        res.remove_header(&CONTENT_TYPE);
+        assert_eq!(None, res.header(&CONTENT_TYPE));
+    }
+
+    #[test]
+    fn body_round_trip() {
+        let mut res = AlbTargetGroupResponse {
+            body: Some(Body::from("aGVsbG8=")),
+            is_base64_encoded: true,
+            ..Default::default()
+        };
+        assert_eq!(Body::from("hello".as_bytes()), res.decoded_body().unwrap());
+
+        res.set_body(Body::from("text"));
+        assert!(!res.is_base64_encoded);
+        assert_eq!(Body::from("text"), res.decoded_body().unwrap());
+    }
+}
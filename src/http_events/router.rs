@@ -0,0 +1,477 @@
+use super::LambdaHttpRequest;
+use super::LambdaHttpResponse;
+use crate::custom_serde::Lenient;
+use crate::encodings::Body;
+use http::header::{HeaderValue, ALLOW, CONTENT_TYPE};
+use http::{Method, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+
+type Handler = Box<dyn Fn(RouteRequest) -> Response<Body> + Send + Sync>;
+
+/// `RouteRequest` is an event matched by a `Router`, with the path parameters of its route.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteRequest<'a> {
+    pub event: &'a LambdaHttpRequest,
+    pub path_parameters: HashMap<String, String>,
+}
+
+impl<'a> RouteRequest<'a> {
+    /// Returns a path parameter by name.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.path_parameters.get(name).map(String::as_str)
+    }
+
+    /// Deserializes the path parameters into `T`, parsing numbers and booleans as needed.
+    pub fn path_params<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        let params = self
+            .path_parameters
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        T::deserialize(Lenient(Value::Object(params)))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Greedy(String),
+}
+
+struct Route {
+    method: Option<Method>,
+    template: String,
+    segments: Vec<Segment>,
+    handler: Handler,
+}
+
+impl Route {
+    /// Matches a raw request path, returning the decoded path parameters.
+    fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        let parts = split_path(path);
+        let mut params = HashMap::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Literal(literal) => {
+                    if parts.get(i) != Some(&literal.as_str()) {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    let part = parts.get(i).filter(|p| !p.is_empty())?;
+                    params.insert(name.clone(), decode(part));
+                }
+                Segment::Greedy(name) => {
+                    if parts.len() <= i {
+                        return None;
+                    }
+                    params.insert(name.clone(), decode(&parts[i..].join("/")));
+                    return Some(params);
+                }
+            }
+        }
+        if parts.len() == self.segments.len() {
+            Some(params)
+        } else {
+            None
+        }
+    }
+
+    /// Ranks routes that match the same path like API Gateway does:
+    /// literal segments win over parameters, and parameters over greedy parameters.
+    fn specificity(&self) -> (usize, usize) {
+        let literals = self
+            .segments
+            .iter()
+            .filter(|s| matches!(s, Segment::Literal(_)))
+            .count();
+        let params = self
+            .segments
+            .iter()
+            .filter(|s| matches!(s, Segment::Param(_)))
+            .count();
+        (literals, params)
+    }
+
+    fn allows(&self, method: &Method) -> bool {
+        match self.method {
+            Some(ref m) => m == method,
+            None => true,
+        }
+    }
+}
+
+/// `Router` dispatches HTTP events to handlers by resource template, e.g. `/users/{id}` or `/{proxy+}`.
+///
+/// REST and HTTP API events are matched on the template of the resource or route that
+/// API Gateway selected, using its `path_parameters`. Events from other sources, and API
+/// Gateway events whose template is not registered, like a `/{proxy+}` integration,
+/// are matched on their raw path.
+///
+/// Requests without a matching route get a `404 Not Found` response, and requests whose
+/// path matches only routes for other methods get a `405 Method Not Allowed` response.
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    /// Creates a router without routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles `method` requests to the resource `template`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a greedy parameter like `{proxy+}` is not the last segment of `template`.
+    pub fn route<F>(mut self, method: Method, template: &str, handler: F) -> Self
+    where
+        F: Fn(RouteRequest) -> Response<Body> + Send + Sync + 'static,
+    {
+        self.add(Some(method), template, Box::new(handler));
+        self
+    }
+
+    /// Handles requests with any method to the resource `template`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a greedy parameter like `{proxy+}` is not the last segment of `template`.
+    pub fn any<F>(mut self, template: &str, handler: F) -> Self
+    where
+        F: Fn(RouteRequest) -> Response<Body> + Send + Sync + 'static,
+    {
+        self.add(None, template, Box::new(handler));
+        self
+    }
+
+    fn add(&mut self, method: Option<Method>, template: &str, handler: Handler) {
+        let segments = parse_template(template);
+        self.routes.push(Route {
+            method,
+            template: format_template(&segments),
+            segments,
+            handler,
+        });
+    }
+
+    /// Calls the handler of the route that matches `event`, and converts its
+    /// response into the response type expected by the event source.
+    pub fn handle(&self, event: &LambdaHttpRequest) -> LambdaHttpResponse {
+        event.build_response(self.dispatch(event))
+    }
+
+    /// Calls the handler of the route that matches `event`.
+    pub fn dispatch(&self, event: &LambdaHttpRequest) -> Response<Body> {
+        let method = event.method();
+
+        if let Some((template, path_parameters)) = matched_template(event) {
+            let routes: Vec<&Route> = self
+                .routes
+                .iter()
+                .filter(|r| r.template == template)
+                .collect();
+            if !routes.is_empty() {
+                return match routes.iter().find(|r| r.allows(&method)) {
+                    Some(route) => (route.handler)(RouteRequest {
+                        event,
+                        path_parameters: path_parameters.clone(),
+                    }),
+                    None => method_not_allowed(&routes),
+                };
+            }
+        }
+
+        let path = event.path().unwrap_or("/");
+        let mut matches: Vec<(&Route, HashMap<String, String>)> = self
+            .routes
+            .iter()
+            .filter_map(|r| r.match_path(path).map(|params| (r, params)))
+            .collect();
+        if matches.is_empty() {
+            return error_response(StatusCode::NOT_FOUND);
+        }
+        // Keep the first registered route among the most specific ones.
+        matches.sort_by_key(|(r, _)| Reverse(r.specificity()));
+
+        match matches.iter().position(|(r, _)| r.allows(&method)) {
+            Some(i) => {
+                let (route, path_parameters) = matches.swap_remove(i);
+                (route.handler)(RouteRequest {
+                    event,
+                    path_parameters,
+                })
+            }
+            None => {
+                let routes: Vec<&Route> = matches.iter().map(|(r, _)| *r).collect();
+                method_not_allowed(&routes)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let routes: Vec<String> = self
+            .routes
+            .iter()
+            .map(|r| match r.method {
+                Some(ref method) => format!("{} {}", method, r.template),
+                None => format!("ANY {}", r.template),
+            })
+            .collect();
+        f.debug_struct("Router").field("routes", &routes).finish()
+    }
+}
+
+/// Returns the resource template that API Gateway matched for the event, with its path parameters.
+fn matched_template(event: &LambdaHttpRequest) -> Option<(String, &HashMap<String, String>)> {
+    let (template, path_parameters) = match event {
+        LambdaHttpRequest::ApiGatewayV1(r) => (r.resource.as_deref()?, &r.path_parameters),
+        LambdaHttpRequest::ApiGatewayV2(r) => {
+            let route_key = r.route_key.as_deref()?;
+            let (_, template) = route_key.split_once(' ')?;
+            (template, &r.path_parameters)
+        }
+        _ => return None,
+    };
+    Some((format_template(&parse_template(template)), path_parameters))
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        Vec::new()
+    } else {
+        path.split('/').collect()
+    }
+}
+
+fn decode(value: &str) -> String {
+    percent_decode_str(value).decode_utf8_lossy().into_owned()
+}
+
+fn parse_template(template: &str) -> Vec<Segment> {
+    let parts = split_path(template);
+    let last = parts.len().saturating_sub(1);
+    parts
+        .iter()
+        .enumerate()
+        .map(
+            |(i, part)| match part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                Some(name) => match name.strip_suffix('+') {
+                    Some(name) => {
+                        assert!(
+                            i == last,
+                            "greedy path parameter {{{}+}} must be the last segment of {}",
+                            name,
+                            template
+                        );
+                        Segment::Greedy(name.to_string())
+                    }
+                    None => Segment::Param(name.to_string()),
+                },
+                None => Segment::Literal(part.to_string()),
+            },
+        )
+        .collect()
+}
+
+fn format_template(segments: &[Segment]) -> String {
+    let mut template = String::new();
+    for segment in segments {
+        template.push('/');
+        match segment {
+            Segment::Literal(literal) => template.push_str(literal),
+            Segment::Param(name) => template.push_str(&format!("{{{}}}", name)),
+            Segment::Greedy(name) => template.push_str(&format!("{{{}+}}", name)),
+        }
+    }
+    if template.is_empty() {
+        template.push('/');
+    }
+    template
+}
+
+fn method_not_allowed(routes: &[&Route]) -> Response<Body> {
+    let mut allowed: Vec<&str> = routes
+        .iter()
+        .filter_map(|r| r.method.as_ref().map(Method::as_str))
+        .collect();
+    allowed.sort_unstable();
+    allowed.dedup();
+
+    let mut res = error_response(StatusCode::METHOD_NOT_ALLOWED);
+    if let Ok(allow) = HeaderValue::from_str(&allowed.join(", ")) {
+        res.headers_mut().insert(ALLOW, allow);
+    }
+    res
+}
+
+/// Builds an error response with the same body as API Gateway's default gateway responses.
+fn error_response(status: StatusCode) -> Response<Body> {
+    let message = status.canonical_reason().unwrap_or_default();
+    let body = json_message(message);
+    let mut res = Response::new(Body::from(body));
+    *res.status_mut() = status;
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    res
+}
+
+fn json_message(message: &str) -> String {
+    #[derive(Serialize)]
+    struct Message<'a> {
+        message: &'a str,
+    }
+    serde_json::to_string(&Message { message }).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::apigw::ApiGatewayV2httpResponse;
+
+    extern crate serde_json;
+
+    fn respond(body: &'static str) -> impl Fn(RouteRequest) -> Response<Body> {
+        move |req| {
+            let params: Vec<String> = {
+                let mut params: Vec<String> = req
+                    .path_parameters
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect();
+                params.sort();
+                params
+            };
+            Response::new(Body::from(format!("{} {}", body, params.join(","))))
+        }
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route(Method::GET, "/users/{id}", respond("get_user"))
+            .route(Method::DELETE, "/users/{id}", respond("delete_user"))
+            .route(Method::GET, "/users/me", respond("me"))
+            .any("/files/{path+}", respond("files"))
+    }
+
+    fn alb_event(method: &str, path: &str) -> LambdaHttpRequest {
+        serde_json::from_value(json!({
+            "httpMethod": method,
+            "path": path,
+            "requestContext": {"elb": {"targetGroupArn": "arn"}},
+            "isBase64Encoded": false
+        }))
+        .unwrap()
+    }
+
+    fn body(res: Response<Body>) -> String {
+        String::from_utf8(res.into_body().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn route_on_raw_path() {
+        let router = router();
+        assert_eq!(
+            "get_user id=42",
+            body(router.dispatch(&alb_event("GET", "/users/42")))
+        );
+        assert_eq!("me ", body(router.dispatch(&alb_event("GET", "/users/me"))));
+        assert_eq!(
+            "delete_user id=a b",
+            body(router.dispatch(&alb_event("DELETE", "/users/a%20b")))
+        );
+        assert_eq!(
+            "files path=a/b/c.txt",
+            body(router.dispatch(&alb_event("PUT", "/files/a/b/c.txt")))
+        );
+    }
+
+    #[test]
+    fn route_not_found_and_method_not_allowed() {
+        let router = router();
+
+        let res = router.dispatch(&alb_event("GET", "/files"));
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+        assert_eq!("application/json", res.headers()[CONTENT_TYPE]);
+        assert_eq!(r#"{"message":"Not Found"}"#, body(res));
+
+        let res = router.dispatch(&alb_event("POST", "/users/42"));
+        assert_eq!(StatusCode::METHOD_NOT_ALLOWED, res.status());
+        assert_eq!("DELETE, GET", res.headers()[ALLOW]);
+        assert_eq!(r#"{"message":"Method Not Allowed"}"#, body(res));
+    }
+
+    #[test]
+    fn route_on_api_gateway_templates() {
+        let router = router();
+        let event: LambdaHttpRequest = serde_json::from_value(json!({
+            "resource": "/users/{id}",
+            "path": "/prod/users/42",
+            "httpMethod": "GET",
+            "pathParameters": {"id": "42"},
+            "requestContext": {"httpMethod": "GET", "requestTimeEpoch": 0}
+        }))
+        .unwrap();
+        assert_eq!("get_user id=42", body(router.dispatch(&event)));
+
+        let event: LambdaHttpRequest = serde_json::from_value(json!({
+            "version": "2.0",
+            "routeKey": "ANY /{proxy+}",
+            "rawPath": "/users/42",
+            "pathParameters": {"proxy": "users/42"},
+            "requestContext": {
+                "timeEpoch": 0,
+                "http": {"method": "DELETE", "path": "/users/42"}
+            }
+        }))
+        .unwrap();
+        match router.handle(&event) {
+            LambdaHttpResponse::ApiGatewayV2(ApiGatewayV2httpResponse { body, .. }) => {
+                assert_eq!(Some(Body::from("delete_user id=42")), body)
+            }
+            not => panic!(
+                "expected LambdaHttpResponse::ApiGatewayV2(...) got {:?}",
+                not
+            ),
+        }
+    }
+
+    #[test]
+    fn deserialize_path_params() {
+        #[derive(Deserialize)]
+        struct UserPath {
+            id: u64,
+        }
+
+        let router = Router::new().route(Method::GET, "/users/{id}", |req| {
+            let status = match req.path_params::<UserPath>() {
+                Ok(path) if path.id == 42 => StatusCode::OK,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            let mut res = Response::new(Body::Empty);
+            *res.status_mut() = status;
+            res
+        });
+        let res = router.dispatch(&alb_event("GET", "/users/42"));
+        assert_eq!(StatusCode::OK, res.status());
+        let res = router.dispatch(&alb_event("GET", "/users/abc"));
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[test]
+    #[should_panic(expected = "must be the last segment")]
+    fn reject_greedy_parameter_in_the_middle() {
+        Router::new().any("/{proxy+}/edit", respond("edit"));
+    }
+}
@@ -0,0 +1,455 @@
+use super::{BinaryMediaTypes, ConversionError};
+use crate::encodings::Body;
+use bytes::Buf;
+use http::header::CONTENT_TYPE;
+use http::response::Parts;
+use http::{Request, Response};
+use http_body::Body as HttpBody;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+use super::{LambdaHttpRequest, LambdaHttpResponse};
+#[cfg(feature = "alb")]
+use crate::alb::{AlbTargetGroupRequest, AlbTargetGroupResponse};
+#[cfg(feature = "apigw")]
+use crate::apigw::{
+    ApiGatewayProxyRequest, ApiGatewayProxyResponse, ApiGatewayV2httpRequest,
+    ApiGatewayV2httpResponse, ApiGatewayWebsocketProxyRequest,
+};
+#[cfg(feature = "lambda_function_urls")]
+use crate::lambda_function_urls::{LambdaFunctionUrlRequest, LambdaFunctionUrlResponse};
+#[cfg(feature = "apigw")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "apigw")]
+use serde::ser::Serialize;
+
+/// `ResponseBuilder` converts an `http::Response` into a response event.
+pub type ResponseBuilder<R> = Box<dyn FnOnce(Response<Body>) -> R + Send>;
+
+/// `HttpRequestEvent` is a Lambda HTTP event that can be served by an `http::Request` handler.
+pub trait HttpRequestEvent {
+    /// The response event expected by the integration that sent the request.
+    type Response;
+
+    /// Returns the conversion from an `http::Response` into the response event for this request.
+    fn response_builder(&self) -> ResponseBuilder<Self::Response>;
+
+    /// Converts the event into an `http::Request`.
+    fn into_http_request(self) -> Result<Request<Body>, ConversionError>;
+}
+
+#[cfg(feature = "apigw")]
+impl<T1> HttpRequestEvent for ApiGatewayProxyRequest<T1>
+where
+    T1: DeserializeOwned + Serialize + Send + Sync + 'static,
+{
+    type Response = ApiGatewayProxyResponse;
+
+    fn response_builder(&self) -> ResponseBuilder<Self::Response> {
+        Box::new(ApiGatewayProxyResponse::from)
+    }
+
+    fn into_http_request(self) -> Result<Request<Body>, ConversionError> {
+        Request::try_from(self)
+    }
+}
+
+#[cfg(feature = "apigw")]
+impl HttpRequestEvent for ApiGatewayV2httpRequest {
+    type Response = ApiGatewayV2httpResponse;
+
+    fn response_builder(&self) -> ResponseBuilder<Self::Response> {
+        Box::new(ApiGatewayV2httpResponse::from)
+    }
+
+    fn into_http_request(self) -> Result<Request<Body>, ConversionError> {
+        Request::try_from(self)
+    }
+}
+
+#[cfg(feature = "apigw")]
+impl<T1, T2> HttpRequestEvent for ApiGatewayWebsocketProxyRequest<T1, T2>
+where
+    T1: DeserializeOwned + Serialize + Send + Sync + 'static,
+    T2: DeserializeOwned + Serialize + Send + Sync + 'static,
+{
+    type Response = ApiGatewayProxyResponse;
+
+    fn response_builder(&self) -> ResponseBuilder<Self::Response> {
+        Box::new(ApiGatewayProxyResponse::from)
+    }
+
+    fn into_http_request(self) -> Result<Request<Body>, ConversionError> {
+        Request::try_from(self)
+    }
+}
+
+/// ALB responses use multi value headers when the request had them.
+#[cfg(feature = "alb")]
+impl HttpRequestEvent for AlbTargetGroupRequest {
+    type Response = AlbTargetGroupResponse;
+
+    fn response_builder(&self) -> ResponseBuilder<Self::Response> {
+        let multi_value_headers = !self.multi_value_headers.is_empty();
+        Box::new(move |res| AlbTargetGroupResponse::from_http_response(res, multi_value_headers))
+    }
+
+    fn into_http_request(self) -> Result<Request<Body>, ConversionError> {
+        Request::try_from(self)
+    }
+}
+
+#[cfg(feature = "lambda_function_urls")]
+impl HttpRequestEvent for LambdaFunctionUrlRequest {
+    type Response = LambdaFunctionUrlResponse;
+
+    fn response_builder(&self) -> ResponseBuilder<Self::Response> {
+        Box::new(LambdaFunctionUrlResponse::from)
+    }
+
+    fn into_http_request(self) -> Result<Request<Body>, ConversionError> {
+        Request::try_from(self)
+    }
+}
+
+#[cfg(all(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+impl HttpRequestEvent for LambdaHttpRequest {
+    type Response = LambdaHttpResponse;
+
+    fn response_builder(&self) -> ResponseBuilder<Self::Response> {
+        let kind = self.response_kind();
+        Box::new(move |res| kind.build_response(res))
+    }
+
+    fn into_http_request(self) -> Result<Request<Body>, ConversionError> {
+        Request::try_from(self)
+    }
+}
+
+/// `EventService` serves Lambda HTTP events with a `tower_service::Service` that handles
+/// `http::Request`s, like an axum router or a hyper service.
+///
+/// Each event is converted into an `http::Request`, and the `http::Response` returned by the
+/// inner service is collected and converted into the response event for that integration.
+#[derive(Clone, Debug)]
+pub struct EventService<S> {
+    inner: S,
+}
+
+impl<S> EventService<S> {
+    /// Wraps a service that handles `http::Request`s.
+    pub fn new(inner: S) -> Self {
+        EventService { inner }
+    }
+
+    /// Returns the wrapped service.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, E, B> Service<E> for EventService<S>
+where
+    S: Service<Request<Body>, Response = Response<B>>,
+    E: HttpRequestEvent,
+    B: HttpBody,
+    B::Error: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    type Response = E::Response;
+    type Error = EventServiceError<S::Error>;
+    type Future = EventServiceFuture<S::Future, B, E::Response>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner
+            .poll_ready(cx)
+            .map_err(EventServiceError::Service)
+    }
+
+    fn call(&mut self, event: E) -> Self::Future {
+        let build_response = event.response_builder();
+        let state = match event.into_http_request() {
+            Ok(req) => State::Call(Box::pin(self.inner.call(req))),
+            Err(e) => State::Invalid(Some(e)),
+        };
+        EventServiceFuture {
+            state,
+            build_response: Some(build_response),
+        }
+    }
+}
+
+/// `EventServiceError` is returned when an `EventService` can not serve an event.
+#[derive(Debug)]
+pub enum EventServiceError<E> {
+    /// The event could not be converted into an `http::Request`.
+    Conversion(ConversionError),
+    /// The inner service failed.
+    Service(E),
+    /// The body of the response could not be read.
+    Body(Box<dyn error::Error + Send + Sync>),
+}
+
+impl<E: fmt::Display> fmt::Display for EventServiceError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventServiceError::Conversion(e) => write!(f, "invalid event: {}", e),
+            EventServiceError::Service(e) => write!(f, "service error: {}", e),
+            EventServiceError::Body(e) => write!(f, "invalid response body: {}", e),
+        }
+    }
+}
+
+impl<E> error::Error for EventServiceError<E>
+where
+    E: error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            EventServiceError::Conversion(e) => Some(e),
+            EventServiceError::Service(e) => Some(e),
+            EventServiceError::Body(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+enum State<F, B> {
+    Invalid(Option<ConversionError>),
+    Call(Pin<Box<F>>),
+    Collect {
+        parts: Option<Parts>,
+        body: Pin<Box<B>>,
+        bytes: Vec<u8>,
+    },
+}
+
+/// `EventServiceFuture` is the response future of an `EventService`.
+pub struct EventServiceFuture<F, B, R> {
+    state: State<F, B>,
+    build_response: Option<ResponseBuilder<R>>,
+}
+
+impl<F, B, R> fmt::Debug for EventServiceFuture<F, B, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventServiceFuture").finish()
+    }
+}
+
+impl<F, B, R, E> Future for EventServiceFuture<F, B, R>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    B: HttpBody,
+    B::Error: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    type Output = Result<R, EventServiceError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            match this.state {
+                State::Invalid(ref mut e) => {
+                    let e = e.take().expect("polled after completion");
+                    return Poll::Ready(Err(EventServiceError::Conversion(e)));
+                }
+                State::Call(ref mut future) => {
+                    let res = match future.as_mut().poll(cx) {
+                        Poll::Ready(res) => res.map_err(EventServiceError::Service)?,
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    let (parts, body) = res.into_parts();
+                    this.state = State::Collect {
+                        parts: Some(parts),
+                        body: Box::pin(body),
+                        bytes: Vec::new(),
+                    };
+                }
+                State::Collect {
+                    ref mut parts,
+                    ref mut body,
+                    ref mut bytes,
+                } => match body.as_mut().poll_data(cx) {
+                    Poll::Ready(Some(Ok(mut data))) => {
+                        while data.has_remaining() {
+                            let chunk = data.chunk();
+                            let len = chunk.len();
+                            bytes.extend_from_slice(chunk);
+                            data.advance(len);
+                        }
+                    }
+                    Poll::Ready(Some(Err(e))) => {
+                        return Poll::Ready(Err(EventServiceError::Body(e.into())))
+                    }
+                    Poll::Ready(None) => {
+                        let parts = parts.take().expect("polled after completion");
+                        let body = response_body(&parts, std::mem::take(bytes));
+                        let res = Response::from_parts(parts, body);
+                        let build_response =
+                            this.build_response.take().expect("polled after completion");
+                        return Poll::Ready(Ok(build_response(res)));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+            }
+        }
+    }
+}
+
+/// Bodies are returned as text unless their `Content-Type` describes binary data
+/// or they are not valid UTF-8.
+fn response_body(parts: &Parts, bytes: Vec<u8>) -> Body {
+    if bytes.is_empty() {
+        return Body::Empty;
+    }
+    let is_binary = match parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(content_type) => BinaryMediaTypes::default().is_binary(content_type),
+        None => false,
+    };
+    if is_binary {
+        return Body::Binary(bytes);
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => Body::Text(text),
+        Err(e) => Body::Binary(e.into_bytes()),
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "alb",
+    feature = "apigw",
+    feature = "lambda_function_urls"
+))]
+mod test {
+    use super::*;
+    use crate::test_util::block_on;
+    use http::header::HeaderValue;
+    use std::convert::Infallible;
+    use std::future::{ready, Ready};
+
+    extern crate serde_json;
+
+    /// Echoes the method, path and body of the request.
+    #[derive(Clone)]
+    struct Echo;
+
+    impl Service<Request<Body>> for Echo {
+        type Response = Response<Body>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<Body>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<Body>) -> Self::Future {
+            let text = format!(
+                "{} {} {}",
+                req.method(),
+                req.uri().path(),
+                String::from_utf8_lossy(req.body())
+            );
+            let mut res = Response::new(Body::from(text));
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+            ready(Ok(res))
+        }
+    }
+
+    /// Returns the binary body `[0, 159, 146, 150]`.
+    struct Binary;
+
+    impl Service<Request<Body>> for Binary {
+        type Response = Response<Body>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<Body>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Request<Body>) -> Self::Future {
+            let mut res = Response::new(Body::from(vec![0, 159, 146, 150]));
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+            ready(Ok(res))
+        }
+    }
+
+    fn serve<S, E>(service: S, event: E) -> E::Response
+    where
+        S: Service<Request<Body>, Response = Response<Body>>,
+        S::Error: fmt::Debug,
+        E: HttpRequestEvent,
+    {
+        block_on(EventService::new(service).call(event)).unwrap()
+    }
+
+    #[test]
+    fn serve_api_gateway_events() {
+        let data = include_bytes!("../fixtures/example-apigw-request.json");
+        let event: ApiGatewayProxyRequest = serde_json::from_slice(data).unwrap();
+        let res = serve(Echo, event);
+        assert_eq!(200, res.status_code);
+        assert_eq!(
+            Some(Body::from("POST /hello/world {\r\n\t\"a\": 1\r\n}")),
+            res.body
+        );
+        assert_eq!(Some(false), res.is_base64_encoded);
+
+        let data = include_bytes!("../fixtures/example-apigw-v2-request-no-authorizer.json");
+        let event: ApiGatewayV2httpRequest = serde_json::from_slice(data).unwrap();
+        let res = serve(Echo, event);
+        assert_eq!(200, res.status_code);
+        assert_eq!("text/plain", res.headers[CONTENT_TYPE]);
+
+        let data = include_bytes!("../fixtures/example-apigw-websocket-request.json");
+        let event: ApiGatewayWebsocketProxyRequest = serde_json::from_slice(data).unwrap();
+        let res: ApiGatewayProxyResponse = serve(Echo, event);
+        assert_eq!(200, res.status_code);
+    }
+
+    #[test]
+    fn serve_alb_and_function_url_events() {
+        let data =
+            include_bytes!("../fixtures/example-alb-lambda-target-request-multivalue-headers.json");
+        let event: AlbTargetGroupRequest = serde_json::from_slice(data).unwrap();
+        let res = serve(Echo, event);
+        assert_eq!(Some("200 OK"), res.status_description.as_deref());
+        assert!(res.headers.is_empty());
+        assert_eq!("text/plain", res.multi_value_headers[CONTENT_TYPE]);
+
+        let data = include_bytes!("../fixtures/example-lambda-function-url-request.json");
+        let event: LambdaHttpRequest = serde_json::from_slice(data).unwrap();
+        match serve(Echo, event) {
+            LambdaHttpResponse::FunctionUrl(res) => assert_eq!(200, res.status_code),
+            not => panic!("expected a function url response got {:?}", not),
+        }
+    }
+
+    #[test]
+    fn serve_binary_response() {
+        let res = serve(Binary, ApiGatewayV2httpRequest::default());
+        assert_eq!(Some(true), res.is_base64_encoded);
+        assert_eq!(Some(Body::Binary(vec![0, 159, 146, 150])), res.body);
+    }
+
+    #[test]
+    fn invalid_event() {
+        let event = ApiGatewayV2httpRequest {
+            body: Some("not base64!".to_string()),
+            is_base64_encoded: true,
+            ..Default::default()
+        };
+        let err = block_on(EventService::new(Echo).call(event)).unwrap_err();
+        assert!(matches!(err, EventServiceError::Conversion(_)));
+    }
+}
@@ -1,5 +1,21 @@
 extern crate base64;
+#[cfg(feature = "compression")]
+extern crate flate2;
 extern crate http_serde;
+#[cfg(feature = "sqs")]
+extern crate md5;
+extern crate percent_encoding;
+#[cfg(feature = "sns_signature")]
+extern crate rsa;
+extern crate serde_urlencoded;
+#[cfg(feature = "sns_signature")]
+extern crate sha1;
+#[cfg(feature = "sns_signature")]
+extern crate sha2;
+#[cfg(feature = "tower")]
+extern crate tower_service;
+#[cfg(feature = "sns_signature")]
+extern crate x509_cert;
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
@@ -163,9 +179,28 @@ pub mod streams;
 /// Encodings used in AWS Lambda json event values.
 pub mod encodings;
 
+/// Conversions between the HTTP event definitions and the `http` crate types.
+#[cfg(any(feature = "alb", feature = "apigw", feature = "lambda_function_urls"))]
+pub mod http_events;
+
 /// AWS Lambda event definitions.
 pub mod event;
 
 pub mod time_window;
 
 mod custom_serde;
+
+#[cfg(all(
+    test,
+    any(
+        feature = "sqs",
+        feature = "sns_signature",
+        all(
+            feature = "tower",
+            feature = "alb",
+            feature = "apigw",
+            feature = "lambda_function_urls"
+        )
+    )
+))]
+mod test_util;
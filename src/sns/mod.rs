@@ -1,9 +1,14 @@
 use crate::custom_serde::*;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+#[cfg(feature = "sns_signature")]
+mod signature;
+#[cfg(feature = "sns_signature")]
+pub use self::signature::*;
+
 /// The `Event` notification event handled by Lambda
 ///
 /// [https://docs.aws.amazon.com/lambda/latest/dg/with-sns.html](https://docs.aws.amazon.com/lambda/latest/dg/with-sns.html)
@@ -78,6 +83,88 @@ pub struct SnsMessage {
     pub message_attributes: HashMap<String, MessageAttribute>,
 }
 
+/// SnsHttpMessage is the JSON document that SNS posts to HTTP and HTTPS subscriptions.
+///
+/// [https://docs.aws.amazon.com/sns/latest/dg/sns-message-and-json-formats.html](https://docs.aws.amazon.com/sns/latest/dg/sns-message-and-json-formats.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SnsHttpMessage {
+    /// The type of SNS message: **Notification**, **SubscriptionConfirmation** or **UnsubscribeConfirmation**.
+    #[serde(rename = "Type")]
+    pub sns_message_type: String,
+
+    /// A Universally Unique Identifier, unique for each message published.
+    pub message_id: String,
+
+    /// A value used with the `ConfirmSubscription` action, in subscription and unsubscribe confirmations.
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// The Amazon Resource Name (ARN) for the topic.
+    pub topic_arn: String,
+
+    /// The Subject parameter specified when the notification was published to the topic.
+    #[serde(default)]
+    pub subject: Option<String>,
+
+    /// The Message value specified when the notification was published,
+    /// or a description of the confirmation.
+    pub message: String,
+
+    /// The time (UTC) when the message was sent, e.g. `2012-04-25T21:49:25.719Z`.
+    ///
+    /// It's kept as sent, since it's part of the signed string.
+    pub timestamp: String,
+
+    /// Version of the Amazon SNS signature used.
+    pub signature_version: String,
+
+    /// Base64-encoded signature of the message.
+    pub signature: String,
+
+    /// The URL to the certificate that was used to sign the message.
+    #[serde(rename = "SigningCertURL")]
+    pub signing_cert_url: String,
+
+    /// The URL to visit to confirm the subscription, in subscription and unsubscribe confirmations.
+    #[serde(default)]
+    #[serde(rename = "SubscribeURL")]
+    pub subscribe_url: Option<String>,
+
+    /// The URL to visit to unsubscribe the endpoint from the topic, in notifications.
+    #[serde(default)]
+    #[serde(rename = "UnsubscribeURL")]
+    pub unsubscribe_url: Option<String>,
+
+    /// The message attributes, in notifications.
+    #[serde(deserialize_with = "deserialize_lambda_map")]
+    #[serde(default)]
+    pub message_attributes: HashMap<String, MessageAttribute>,
+}
+
+/// The timestamp is formatted with millisecond precision, like SNS sends it.
+impl From<SnsMessage> for SnsHttpMessage {
+    fn from(message: SnsMessage) -> Self {
+        SnsHttpMessage {
+            sns_message_type: message.sns_message_type,
+            message_id: message.message_id,
+            token: None,
+            topic_arn: message.topic_arn,
+            subject: message.subject,
+            message: message.message,
+            timestamp: message
+                .timestamp
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            signature_version: message.signature_version,
+            signature: message.signature,
+            signing_cert_url: message.signing_cert_url,
+            subscribe_url: None,
+            unsubscribe_url: Some(message.unsubscribe_url),
+            message_attributes: message.message_attributes,
+        }
+    }
+}
+
 /// An alternate `Event` notification event to use alongside `SnsRecordObj<T>` and `SnsMessageObj<T>` if you want to deserialize an object inside your SNS messages rather than getting an `Option<String>` message
 ///
 /// [https://docs.aws.amazon.com/lambda/latest/dg/with-sns.html](https://docs.aws.amazon.com/lambda/latest/dg/with-sns.html)
@@ -0,0 +1,563 @@
+use super::{SnsHttpMessage, SnsMessage};
+use http::Uri;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::{error, fmt};
+use x509_cert::der::{DecodePem, Encode};
+use x509_cert::Certificate;
+
+const NOTIFICATION: &str = "Notification";
+const SUBSCRIPTION_CONFIRMATION: &str = "SubscriptionConfirmation";
+const UNSUBSCRIBE_CONFIRMATION: &str = "UnsubscribeConfirmation";
+
+/// `SignatureVersion` is the algorithm used by SNS to sign messages.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignatureVersion {
+    /// `1`, SHA1withRSA
+    V1,
+    /// `2`, SHA256withRSA
+    V2,
+}
+
+impl SignatureVersion {
+    fn parse(version: &str) -> Option<SignatureVersion> {
+        match version {
+            "1" => Some(SignatureVersion::V1),
+            "2" => Some(SignatureVersion::V2),
+            _ => None,
+        }
+    }
+
+    fn verify(&self, key: &RsaPublicKey, data: &[u8], signature: &[u8]) -> bool {
+        let result = match self {
+            SignatureVersion::V1 => {
+                key.verify(Pkcs1v15Sign::new::<Sha1>(), &Sha1::digest(data), signature)
+            }
+            SignatureVersion::V2 => key.verify(
+                Pkcs1v15Sign::new::<Sha256>(),
+                &Sha256::digest(data),
+                signature,
+            ),
+        };
+        result.is_ok()
+    }
+}
+
+/// `SignatureError` is returned when the signature of an SNS message can't be verified.
+#[derive(Debug)]
+pub enum SignatureError<E> {
+    /// The message type is not one of the types signed by SNS.
+    UnsupportedMessageType(String),
+    /// The signature version is not `1` or `2`.
+    UnsupportedSignatureVersion(String),
+    /// A field that is part of the signed string is missing.
+    MissingField(&'static str),
+    /// The certificate URL is not an HTTPS URL on an SNS domain.
+    InvalidCertificateUrl(String),
+    /// The certificate could not be fetched.
+    Fetch(E),
+    /// The certificate is not a PEM encoded X.509 certificate with an RSA key.
+    InvalidCertificate(String),
+    /// The signature doesn't match the message.
+    InvalidSignature,
+}
+
+impl<E: fmt::Display> fmt::Display for SignatureError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignatureError::UnsupportedMessageType(t) => {
+                write!(f, "unsupported SNS message type: {}", t)
+            }
+            SignatureError::UnsupportedSignatureVersion(v) => {
+                write!(f, "unsupported SNS signature version: {}", v)
+            }
+            SignatureError::MissingField(name) => {
+                write!(f, "missing {} in signed SNS message", name)
+            }
+            SignatureError::InvalidCertificateUrl(url) => {
+                write!(f, "invalid SNS signing certificate URL: {}", url)
+            }
+            SignatureError::Fetch(e) => write!(f, "failed to fetch signing certificate: {}", e),
+            SignatureError::InvalidCertificate(e) => {
+                write!(f, "invalid signing certificate: {}", e)
+            }
+            SignatureError::InvalidSignature => write!(f, "invalid SNS message signature"),
+        }
+    }
+}
+
+impl<E> error::Error for SignatureError<E>
+where
+    E: error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SignatureError::Fetch(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// `StringToSignError` is returned when the string signed by SNS can't be built for a message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StringToSignError {
+    /// The message type is not one of the types signed by SNS.
+    UnsupportedMessageType(String),
+    /// A field that is part of the signed string is missing.
+    MissingField(&'static str),
+}
+
+impl fmt::Display for StringToSignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StringToSignError::UnsupportedMessageType(t) => {
+                write!(f, "unsupported SNS message type: {}", t)
+            }
+            StringToSignError::MissingField(name) => {
+                write!(f, "missing {} in signed SNS message", name)
+            }
+        }
+    }
+}
+
+impl error::Error for StringToSignError {}
+
+impl<E> From<StringToSignError> for SignatureError<E> {
+    fn from(e: StringToSignError) -> Self {
+        match e {
+            StringToSignError::UnsupportedMessageType(t) => {
+                SignatureError::UnsupportedMessageType(t)
+            }
+            StringToSignError::MissingField(name) => SignatureError::MissingField(name),
+        }
+    }
+}
+
+/// Returns the string that SNS signs for a message.
+///
+/// Notifications sign `Message`, `MessageId`, `Subject` (if present), `Timestamp`, `TopicArn`
+/// and `Type`. Confirmations sign `Message`, `MessageId`, `SubscribeURL`, `Timestamp`,
+/// `Token`, `TopicArn` and `Type`. Every name and value is followed by a newline.
+pub fn string_to_sign(message: &SnsHttpMessage) -> Result<String, StringToSignError> {
+    let timestamp = message.timestamp.as_str();
+    let mut fields = vec![
+        ("Message", Some(message.message.as_str())),
+        ("MessageId", Some(message.message_id.as_str())),
+    ];
+    match message.sns_message_type.as_str() {
+        NOTIFICATION => {
+            if let Some(ref subject) = message.subject {
+                fields.push(("Subject", Some(subject)));
+            }
+            fields.push(("Timestamp", Some(timestamp)));
+        }
+        SUBSCRIPTION_CONFIRMATION | UNSUBSCRIBE_CONFIRMATION => {
+            fields.push(("SubscribeURL", message.subscribe_url.as_deref()));
+            fields.push(("Timestamp", Some(timestamp)));
+            fields.push(("Token", message.token.as_deref()));
+        }
+        other => return Err(StringToSignError::UnsupportedMessageType(other.to_string())),
+    }
+    fields.push(("TopicArn", Some(message.topic_arn.as_str())));
+    fields.push(("Type", Some(message.sns_message_type.as_str())));
+
+    let mut string = String::new();
+    for (name, value) in fields {
+        let value = value.ok_or(StringToSignError::MissingField(name))?;
+        string.push_str(name);
+        string.push('\n');
+        string.push_str(value);
+        string.push('\n');
+    }
+    Ok(string)
+}
+
+/// Returns true when `url` is an HTTPS URL on an SNS domain,
+/// e.g. `https://sns.us-east-1.amazonaws.com/SimpleNotificationService-0123456789abcdef.pem`.
+pub fn is_sns_certificate_url(url: &str) -> bool {
+    let uri: Uri = match url.parse() {
+        Ok(uri) => uri,
+        Err(_) => return false,
+    };
+    let host = match uri.host() {
+        Some(host) => host,
+        None => return false,
+    };
+    let region = host
+        .strip_prefix("sns.")
+        .and_then(|h| {
+            h.strip_suffix(".amazonaws.com")
+                .or_else(|| h.strip_suffix(".amazonaws.com.cn"))
+        })
+        .unwrap_or_default();
+    uri.scheme_str() == Some("https")
+        && uri.port().is_none()
+        && uri.path().ends_with(".pem")
+        && !region.is_empty()
+        && region
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// `CertificateFetcher` downloads the PEM encoded certificates that SNS uses to sign messages.
+pub trait CertificateFetcher {
+    type Error;
+    type Future: Future<Output = Result<Vec<u8>, Self::Error>>;
+
+    /// Fetches the certificate at `url`, which has already been validated as an SNS URL.
+    fn fetch(&self, url: &str) -> Self::Future;
+}
+
+fn public_key<E>(pem: &[u8]) -> Result<RsaPublicKey, SignatureError<E>> {
+    let invalid = |e: &dyn fmt::Display| SignatureError::InvalidCertificate(e.to_string());
+    let certificate = Certificate::from_pem(pem).map_err(|e| invalid(&e))?;
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| invalid(&e))?;
+    RsaPublicKey::from_public_key_der(&spki).map_err(|e| invalid(&e))
+}
+
+/// `SnsSignatureVerifier` verifies the signatures of SNS messages.
+///
+/// Certificates are fetched with a `CertificateFetcher` the first time a URL is seen,
+/// and their keys are cached for the following messages.
+pub struct SnsSignatureVerifier<F> {
+    fetcher: F,
+    keys: Mutex<HashMap<String, RsaPublicKey>>,
+}
+
+impl<F> fmt::Debug for SnsSignatureVerifier<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SnsSignatureVerifier").finish()
+    }
+}
+
+impl<F: CertificateFetcher> SnsSignatureVerifier<F> {
+    /// Creates a verifier that fetches certificates with `fetcher`.
+    pub fn new(fetcher: F) -> Self {
+        SnsSignatureVerifier {
+            fetcher,
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verifies the signature of a message posted to an HTTP subscription.
+    pub fn verify(&self, message: &SnsHttpMessage) -> VerifySignature<'_, F> {
+        let state = match self.prepare(message) {
+            Ok(state) => state,
+            Err(e) => State::Done(Some(Err(e))),
+        };
+        VerifySignature {
+            verifier: self,
+            state,
+        }
+    }
+
+    /// Verifies the signature of a notification delivered to Lambda or SQS.
+    pub fn verify_notification(&self, message: &SnsMessage) -> VerifySignature<'_, F> {
+        self.verify(&SnsHttpMessage::from(message.clone()))
+    }
+
+    fn prepare(&self, message: &SnsHttpMessage) -> Result<State<F>, SignatureError<F::Error>> {
+        let version = SignatureVersion::parse(&message.signature_version).ok_or_else(|| {
+            SignatureError::UnsupportedSignatureVersion(message.signature_version.clone())
+        })?;
+        let url = &message.signing_cert_url;
+        if !is_sns_certificate_url(url) {
+            return Err(SignatureError::InvalidCertificateUrl(url.clone()));
+        }
+        let data = string_to_sign(message)?.into_bytes();
+        let signature =
+            base64::decode(&message.signature).map_err(|_| SignatureError::InvalidSignature)?;
+        let signed = Signed {
+            version,
+            data,
+            signature,
+        };
+
+        let cached = self.keys.lock().unwrap().get(url).cloned();
+        Ok(match cached {
+            Some(key) => State::Done(Some(signed.verify(&key))),
+            None => State::Fetch {
+                url: url.clone(),
+                future: Box::pin(self.fetcher.fetch(url)),
+                signed,
+            },
+        })
+    }
+}
+
+struct Signed {
+    version: SignatureVersion,
+    data: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl Signed {
+    fn verify<E>(&self, key: &RsaPublicKey) -> Result<(), SignatureError<E>> {
+        if self.version.verify(key, &self.data, &self.signature) {
+            Ok(())
+        } else {
+            Err(SignatureError::InvalidSignature)
+        }
+    }
+}
+
+enum State<F: CertificateFetcher> {
+    Done(Option<Result<(), SignatureError<F::Error>>>),
+    Fetch {
+        url: String,
+        future: Pin<Box<F::Future>>,
+        signed: Signed,
+    },
+}
+
+/// `VerifySignature` is the future returned by `SnsSignatureVerifier::verify`.
+pub struct VerifySignature<'a, F: CertificateFetcher> {
+    verifier: &'a SnsSignatureVerifier<F>,
+    state: State<F>,
+}
+
+impl<'a, F: CertificateFetcher> fmt::Debug for VerifySignature<'a, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VerifySignature").finish()
+    }
+}
+
+impl<'a, F: CertificateFetcher> Unpin for VerifySignature<'a, F> {}
+
+impl<'a, F: CertificateFetcher> Future for VerifySignature<'a, F> {
+    type Output = Result<(), SignatureError<F::Error>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let result = match this.state {
+            State::Done(ref mut result) => result.take().expect("polled after completion"),
+            State::Fetch {
+                ref url,
+                ref mut future,
+                ref signed,
+            } => {
+                let pem = match future.as_mut().poll(cx) {
+                    Poll::Ready(pem) => pem,
+                    Poll::Pending => return Poll::Pending,
+                };
+                pem.map_err(SignatureError::Fetch)
+                    .and_then(|pem| public_key(&pem))
+                    .and_then(|key| {
+                        let result = signed.verify(&key);
+                        this.verifier.keys.lock().unwrap().insert(url.clone(), key);
+                        result
+                    })
+            }
+        };
+        this.state = State::Done(None);
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::block_on;
+    use rsa::pkcs8::DecodePrivateKey;
+    use rsa::RsaPrivateKey;
+    use std::cell::Cell;
+    use std::future;
+
+    extern crate serde_json;
+
+    const CERT_URL: &str =
+        "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-0123456789abcdef.pem";
+
+    #[derive(Default)]
+    struct TestCertificates {
+        fetched: Cell<usize>,
+    }
+
+    impl CertificateFetcher for TestCertificates {
+        type Error = String;
+        type Future = future::Ready<Result<Vec<u8>, String>>;
+
+        fn fetch(&self, url: &str) -> Self::Future {
+            self.fetched.set(self.fetched.get() + 1);
+            future::ready(if url == CERT_URL {
+                Ok(include_bytes!("../fixtures/example-sns-signing-cert.pem").to_vec())
+            } else {
+                Err(format!("not found: {}", url))
+            })
+        }
+    }
+
+    fn sign(message: &mut SnsHttpMessage) {
+        let key = RsaPrivateKey::from_pkcs8_der(include_bytes!(
+            "../fixtures/example-sns-signing-key.der"
+        ))
+        .unwrap();
+        let data = string_to_sign(message).unwrap();
+        let signature = match message.signature_version.as_str() {
+            "1" => key.sign(Pkcs1v15Sign::new::<Sha1>(), &Sha1::digest(data.as_bytes())),
+            _ => key.sign(
+                Pkcs1v15Sign::new::<Sha256>(),
+                &Sha256::digest(data.as_bytes()),
+            ),
+        };
+        message.signature = base64::encode(signature.unwrap());
+    }
+
+    fn notification() -> SnsHttpMessage {
+        let data = include_bytes!("../fixtures/example-sns-event.json");
+        let event: super::super::SnsEvent = serde_json::from_slice(data).unwrap();
+        let mut message = SnsHttpMessage::from(event.records[0].sns.clone());
+        message.signing_cert_url = CERT_URL.to_string();
+        message
+    }
+
+    fn subscription_confirmation() -> SnsHttpMessage {
+        serde_json::from_value(json!({
+            "Type": "SubscriptionConfirmation",
+            "MessageId": "165545c9-2a5c-472c-8df2-7ff2be2b3b1b",
+            "Token": "2336412f37fb687f5d51e6e241d09c805a5a57b30d712f794cc5f6a988666d92768dd60a747ba6f3beb71854e285d6ad02428b09ceece29417f1f02d609c582afbacc99c583a916b9981dd2728f4ae6fdb82efd087cc3b7849e05798d2d2785c03b0879594eeac82c01f235d0e717736",
+            "TopicArn": "arn:aws:sns:us-west-2:123456789012:MyTopic",
+            "Message": "You have chosen to subscribe to the topic arn:aws:sns:us-west-2:123456789012:MyTopic.\nTo confirm the subscription, visit the SubscribeURL included in this message.",
+            "SubscribeURL": "https://sns.us-west-2.amazonaws.com/?Action=ConfirmSubscription&TopicArn=arn:aws:sns:us-west-2:123456789012:MyTopic&Token=2336412f37",
+            "Timestamp": "2012-04-26T20:45:04.751Z",
+            "SignatureVersion": "2",
+            "Signature": "",
+            "SigningCertURL": CERT_URL
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn notification_string_to_sign() {
+        let message = notification();
+        assert_eq!(
+            "Message\nHello\r\nworld!\nMessageId\n82833b5c-8d5d-56d0-b0e1-7511f8253eb8\nSubject\nGreetings\nTimestamp\n2015-08-18T18:02:32.111Z\nTopicArn\narn:aws:sns:us-east-1:246796806071:snsNetTest\nType\nNotification\n",
+            string_to_sign(&message).unwrap()
+        );
+    }
+
+    #[test]
+    fn confirmation_string_to_sign() {
+        let mut message = subscription_confirmation();
+        let string = string_to_sign(&message).unwrap();
+        assert!(string.contains("\nSubscribeURL\nhttps://sns.us-west-2.amazonaws.com/"));
+        assert!(string.contains("\nTimestamp\n2012-04-26T20:45:04.751Z\nToken\n2336412f37"));
+        assert!(string.ends_with("\nType\nSubscriptionConfirmation\n"));
+
+        message.token = None;
+        match string_to_sign(&message) {
+            Err(StringToSignError::MissingField("Token")) => {}
+            not => panic!("expected MissingField got {:?}", not),
+        }
+        message.sns_message_type = "Other".to_string();
+        match string_to_sign(&message) {
+            Err(StringToSignError::UnsupportedMessageType(_)) => {}
+            not => panic!("expected UnsupportedMessageType got {:?}", not),
+        }
+    }
+
+    #[test]
+    fn sns_certificate_urls() {
+        assert!(is_sns_certificate_url(CERT_URL));
+        assert!(is_sns_certificate_url(
+            "https://sns.cn-north-1.amazonaws.com.cn/SimpleNotificationService-0123.pem"
+        ));
+        assert!(!is_sns_certificate_url(
+            "http://sns.us-east-1.amazonaws.com/SimpleNotificationService-0123.pem"
+        ));
+        assert!(!is_sns_certificate_url(
+            "https://sns.us-east-1.amazonaws.com.evil.com/cert.pem"
+        ));
+        assert!(!is_sns_certificate_url(
+            "https://evil.com/sns.us-east-1.amazonaws.com.pem"
+        ));
+        assert!(!is_sns_certificate_url(
+            "https://sns.us-east-1.amazonaws.com:8443/cert.pem"
+        ));
+        assert!(!is_sns_certificate_url(
+            "https://sns.us-east-1.amazonaws.com/"
+        ));
+        assert!(!is_sns_certificate_url("not a url"));
+    }
+
+    #[test]
+    fn verify_signatures_and_cache_certificates() {
+        let verifier = SnsSignatureVerifier::new(TestCertificates::default());
+
+        let mut message = notification();
+        sign(&mut message);
+        block_on(verifier.verify(&message)).unwrap();
+
+        let mut message = subscription_confirmation();
+        sign(&mut message);
+        block_on(verifier.verify(&message)).unwrap();
+        assert_eq!(1, verifier.fetcher.fetched.get());
+
+        // The timestamp is signed as it was sent, whatever its precision.
+        let mut microseconds = subscription_confirmation();
+        microseconds.timestamp = "2012-04-26T20:45:04.751123Z".to_string();
+        sign(&mut microseconds);
+        block_on(verifier.verify(&microseconds)).unwrap();
+
+        message.message = "forged".to_string();
+        match block_on(verifier.verify(&message)) {
+            Err(SignatureError::InvalidSignature) => {}
+            not => panic!("expected InvalidSignature got {:?}", not),
+        }
+    }
+
+    #[test]
+    fn verify_notifications_from_events() {
+        let verifier = SnsSignatureVerifier::new(TestCertificates::default());
+        let mut message = notification();
+        message.signature_version = "2".to_string();
+        sign(&mut message);
+
+        let data = include_bytes!("../fixtures/example-sns-event.json");
+        let event: super::super::SnsEvent = serde_json::from_slice(data).unwrap();
+        let mut sns = event.records[0].sns.clone();
+        sns.signing_cert_url = CERT_URL.to_string();
+        sns.signature_version = "2".to_string();
+        sns.signature = message.signature;
+        block_on(verifier.verify_notification(&sns)).unwrap();
+    }
+
+    #[test]
+    fn reject_untrusted_messages() {
+        let verifier = SnsSignatureVerifier::new(TestCertificates::default());
+
+        let mut message = notification();
+        message.signing_cert_url = "https://example.com/cert.pem".to_string();
+        match block_on(verifier.verify(&message)) {
+            Err(SignatureError::InvalidCertificateUrl(_)) => {}
+            not => panic!("expected InvalidCertificateUrl got {:?}", not),
+        }
+
+        let mut message = notification();
+        message.signature_version = "3".to_string();
+        match block_on(verifier.verify(&message)) {
+            Err(SignatureError::UnsupportedSignatureVersion(_)) => {}
+            not => panic!("expected UnsupportedSignatureVersion got {:?}", not),
+        }
+
+        let mut message = notification();
+        message.signing_cert_url =
+            "https://sns.eu-west-1.amazonaws.com/SimpleNotificationService-missing.pem".to_string();
+        match block_on(verifier.verify(&message)) {
+            Err(SignatureError::Fetch(_)) => {}
+            not => panic!("expected Fetch got {:?}", not),
+        }
+        assert_eq!(0, verifier.keys.lock().unwrap().len());
+    }
+}
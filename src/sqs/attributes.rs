@@ -0,0 +1,459 @@
+use super::{SqsMessage, SqsMessageAttribute, SqsMessageObj};
+use crate::custom_serde::Lenient;
+use crate::encodings::Base64Data;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::{error, fmt};
+
+/// `MessageAttributeError` is returned when a message attribute doesn't have the expected type or value.
+#[derive(Debug)]
+pub enum MessageAttributeError {
+    /// The data type is missing, or is not based on `String`, `Number` or `Binary`.
+    InvalidDataType(Option<String>),
+    /// The attribute has no value for its data type.
+    MissingValue(String),
+    /// The value is not a number, or doesn't fit the requested number type.
+    InvalidNumber(String),
+    /// The value could not be deserialized.
+    Deserialize(serde_json::Error),
+}
+
+impl fmt::Display for MessageAttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MessageAttributeError::InvalidDataType(Some(data_type)) => {
+                write!(f, "invalid message attribute data type: {}", data_type)
+            }
+            MessageAttributeError::InvalidDataType(None) => {
+                write!(f, "missing message attribute data type")
+            }
+            MessageAttributeError::MissingValue(data_type) => {
+                write!(
+                    f,
+                    "missing value for message attribute of type {}",
+                    data_type
+                )
+            }
+            MessageAttributeError::InvalidNumber(value) => {
+                write!(f, "invalid number message attribute: {}", value)
+            }
+            MessageAttributeError::Deserialize(e) => {
+                write!(f, "invalid message attribute value: {}", e)
+            }
+        }
+    }
+}
+
+impl error::Error for MessageAttributeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MessageAttributeError::Deserialize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for MessageAttributeError {
+    fn from(e: serde_json::Error) -> Self {
+        MessageAttributeError::Deserialize(e)
+    }
+}
+
+/// `MessageAttributeValue` is the typed value of an `SqsMessageAttribute`.
+///
+/// Data types can have a custom type after a dot, e.g. `Number.float` or `String.json`,
+/// which is kept in `custom_type`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MessageAttributeValue {
+    String {
+        value: String,
+        custom_type: Option<String>,
+    },
+    /// A number, kept as the decimal string sent by SQS.
+    Number {
+        value: String,
+        custom_type: Option<String>,
+    },
+    Binary {
+        value: Vec<u8>,
+        custom_type: Option<String>,
+    },
+}
+
+impl MessageAttributeValue {
+    /// Creates a `String` attribute.
+    pub fn string<S: Into<String>>(value: S) -> Self {
+        MessageAttributeValue::String {
+            value: value.into(),
+            custom_type: None,
+        }
+    }
+
+    /// Creates a `Number` attribute.
+    pub fn number<N: ToString>(value: N) -> Self {
+        MessageAttributeValue::Number {
+            value: value.to_string(),
+            custom_type: None,
+        }
+    }
+
+    /// Creates a `Binary` attribute.
+    pub fn binary<B: Into<Vec<u8>>>(value: B) -> Self {
+        MessageAttributeValue::Binary {
+            value: value.into(),
+            custom_type: None,
+        }
+    }
+
+    /// Sets the custom type, e.g. `json` for a `String.json` attribute.
+    pub fn with_custom_type(mut self, custom: &str) -> Self {
+        match self {
+            MessageAttributeValue::String {
+                ref mut custom_type,
+                ..
+            }
+            | MessageAttributeValue::Number {
+                ref mut custom_type,
+                ..
+            }
+            | MessageAttributeValue::Binary {
+                ref mut custom_type,
+                ..
+            } => *custom_type = Some(custom.to_string()),
+        }
+        self
+    }
+
+    /// Returns the full data type, e.g. `Number.float`.
+    pub fn data_type(&self) -> String {
+        let base = match self {
+            MessageAttributeValue::String { .. } => "String",
+            MessageAttributeValue::Number { .. } => "Number",
+            MessageAttributeValue::Binary { .. } => "Binary",
+        };
+        match self.custom_type() {
+            Some(custom_type) => format!("{}.{}", base, custom_type),
+            None => base.to_string(),
+        }
+    }
+
+    /// Returns the custom type, e.g. `float` for a `Number.float` attribute.
+    pub fn custom_type(&self) -> Option<&str> {
+        match self {
+            MessageAttributeValue::String { custom_type, .. }
+            | MessageAttributeValue::Number { custom_type, .. }
+            | MessageAttributeValue::Binary { custom_type, .. } => custom_type.as_deref(),
+        }
+    }
+
+    /// Returns the value of `String` and `Number` attributes.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MessageAttributeValue::String { value, .. }
+            | MessageAttributeValue::Number { value, .. } => Some(value),
+            MessageAttributeValue::Binary { .. } => None,
+        }
+    }
+
+    /// Returns the value of `Binary` attributes.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            MessageAttributeValue::Binary { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Parses the value of a `Number` attribute, e.g. as an `i64` or an `f64`.
+    pub fn parse_number<N: FromStr>(&self) -> Result<N, MessageAttributeError> {
+        match self {
+            MessageAttributeValue::Number { value, .. } => value
+                .trim()
+                .parse()
+                .map_err(|_| MessageAttributeError::InvalidNumber(value.clone())),
+            _ => Err(MessageAttributeError::InvalidNumber(
+                self.as_str().unwrap_or_default().to_string(),
+            )),
+        }
+    }
+
+    /// Deserializes a JSON document stored in the attribute, e.g. in a `String.json` attribute.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, MessageAttributeError> {
+        let value = match self {
+            MessageAttributeValue::String { value, .. }
+            | MessageAttributeValue::Number { value, .. } => serde_json::from_str(value),
+            MessageAttributeValue::Binary { value, .. } => serde_json::from_slice(value),
+        };
+        value.map_err(MessageAttributeError::Deserialize)
+    }
+
+    /// Converts the value into JSON for `deserialize_message_attributes`:
+    /// `json` strings are parsed, numbers are kept as strings for `Lenient`,
+    /// and binary values become arrays of bytes.
+    fn into_json(self) -> Result<Value, MessageAttributeError> {
+        match self {
+            MessageAttributeValue::String {
+                value,
+                custom_type: Some(ref custom_type),
+            } if custom_type.eq_ignore_ascii_case("json") => {
+                serde_json::from_str(&value).map_err(MessageAttributeError::Deserialize)
+            }
+            MessageAttributeValue::String { value, .. }
+            | MessageAttributeValue::Number { value, .. } => Ok(Value::String(value)),
+            MessageAttributeValue::Binary { value, .. } => {
+                Ok(Value::Array(value.into_iter().map(Value::from).collect()))
+            }
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a SqsMessageAttribute> for MessageAttributeValue {
+    type Error = MessageAttributeError;
+
+    fn try_from(attribute: &'a SqsMessageAttribute) -> Result<Self, Self::Error> {
+        let data_type = attribute
+            .data_type
+            .as_deref()
+            .ok_or(MessageAttributeError::InvalidDataType(None))?;
+        let mut parts = data_type.splitn(2, '.');
+        let base = parts.next().unwrap_or_default();
+        let custom_type = parts.next().map(String::from);
+        let missing = || MessageAttributeError::MissingValue(data_type.to_string());
+
+        match base {
+            "String" => Ok(MessageAttributeValue::String {
+                value: attribute.string_value.clone().ok_or_else(missing)?,
+                custom_type,
+            }),
+            "Number" => Ok(MessageAttributeValue::Number {
+                value: attribute.string_value.clone().ok_or_else(missing)?,
+                custom_type,
+            }),
+            "Binary" => Ok(MessageAttributeValue::Binary {
+                value: attribute.binary_value.clone().ok_or_else(missing)?.0,
+                custom_type,
+            }),
+            _ => Err(MessageAttributeError::InvalidDataType(Some(
+                data_type.to_string(),
+            ))),
+        }
+    }
+}
+
+impl From<MessageAttributeValue> for SqsMessageAttribute {
+    fn from(value: MessageAttributeValue) -> Self {
+        let data_type = Some(value.data_type());
+        match value {
+            MessageAttributeValue::String { value, .. }
+            | MessageAttributeValue::Number { value, .. } => SqsMessageAttribute {
+                string_value: Some(value),
+                data_type,
+                ..Default::default()
+            },
+            MessageAttributeValue::Binary { value, .. } => SqsMessageAttribute {
+                binary_value: Some(Base64Data(value)),
+                data_type,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl SqsMessageAttribute {
+    /// Returns the typed value of the attribute.
+    pub fn value(&self) -> Result<MessageAttributeValue, MessageAttributeError> {
+        MessageAttributeValue::try_from(self)
+    }
+}
+
+fn deserialize_message_attributes<T: DeserializeOwned>(
+    attributes: &HashMap<String, SqsMessageAttribute>,
+) -> Result<T, MessageAttributeError> {
+    let mut values = Map::new();
+    for (name, attribute) in attributes {
+        values.insert(name.clone(), attribute.value()?.into_json()?);
+    }
+    Ok(T::deserialize(Lenient(Value::Object(values)))?)
+}
+
+fn deserialize_attributes<T: DeserializeOwned>(
+    attributes: &HashMap<String, String>,
+) -> Result<T, serde_json::Error> {
+    let values = attributes
+        .iter()
+        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+        .collect();
+    T::deserialize(Lenient(Value::Object(values)))
+}
+
+impl SqsMessage {
+    /// Deserializes every message attribute into `T`, by attribute name.
+    ///
+    /// `Number` attributes are parsed when the field is a number, `String.json` attributes
+    /// are parsed as JSON documents, and `Binary` attributes deserialize into byte vectors.
+    pub fn deserialize_message_attributes<T: DeserializeOwned>(
+        &self,
+    ) -> Result<T, MessageAttributeError> {
+        deserialize_message_attributes(&self.message_attributes)
+    }
+
+    /// Deserializes the system attributes into `T`, parsing numbers when the field is a number.
+    pub fn deserialize_attributes<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        deserialize_attributes(&self.attributes)
+    }
+}
+
+impl<T: Serialize> SqsMessageObj<T> {
+    /// Deserializes every message attribute into `A`, by attribute name.
+    ///
+    /// `Number` attributes are parsed when the field is a number, `String.json` attributes
+    /// are parsed as JSON documents, and `Binary` attributes deserialize into byte vectors.
+    pub fn deserialize_message_attributes<A: DeserializeOwned>(
+        &self,
+    ) -> Result<A, MessageAttributeError> {
+        deserialize_message_attributes(&self.message_attributes)
+    }
+
+    /// Deserializes the system attributes into `A`, parsing numbers when the field is a number.
+    pub fn deserialize_attributes<A: DeserializeOwned>(&self) -> Result<A, serde_json::Error> {
+        deserialize_attributes(&self.attributes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::SqsEvent;
+    use super::*;
+
+    extern crate serde_json;
+
+    fn example_message() -> SqsMessage {
+        let data = include_bytes!("../fixtures/example-sqs-event.json");
+        let mut event: SqsEvent = serde_json::from_slice(data).unwrap();
+        event.records.remove(0)
+    }
+
+    #[test]
+    fn typed_message_attribute_values() {
+        let message = example_message();
+        let value = message.message_attributes["Attribute1"].value().unwrap();
+        assert_eq!(MessageAttributeValue::string("AttributeValue1"), value);
+        assert_eq!(Some("AttributeValue1"), value.as_str());
+
+        let value = message.message_attributes["Attribute2"].value().unwrap();
+        assert_eq!(123, value.parse_number::<i64>().unwrap());
+        assert_eq!(123.0, value.parse_number::<f64>().unwrap());
+        assert!(value.parse_number::<u8>().is_ok());
+
+        let value = message.message_attributes["Attribute3"].value().unwrap();
+        assert_eq!(Some("1100".as_bytes()), value.as_bytes());
+        assert_eq!("Binary", value.data_type());
+        assert!(matches!(
+            value.parse_number::<i64>(),
+            Err(MessageAttributeError::InvalidNumber(_))
+        ));
+    }
+
+    #[test]
+    fn custom_message_attribute_types() {
+        let attribute = SqsMessageAttribute {
+            string_value: Some("1.5".to_string()),
+            data_type: Some("Number.float".to_string()),
+            ..Default::default()
+        };
+        let value = attribute.value().unwrap();
+        assert_eq!(Some("float"), value.custom_type());
+        assert_eq!("Number.float", value.data_type());
+        assert_eq!(1.5, value.parse_number::<f64>().unwrap());
+        assert!(value.parse_number::<i64>().is_err());
+        assert_eq!(attribute, SqsMessageAttribute::from(value));
+
+        let value = MessageAttributeValue::string(r#"{"a":[1,2]}"#).with_custom_type("json");
+        let json: Value = value.json().unwrap();
+        assert_eq!(json!({"a": [1, 2]}), json);
+    }
+
+    #[test]
+    fn invalid_message_attributes() {
+        let attribute = SqsMessageAttribute::default();
+        assert!(matches!(
+            attribute.value(),
+            Err(MessageAttributeError::InvalidDataType(None))
+        ));
+
+        let attribute = SqsMessageAttribute {
+            data_type: Some("Number".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            attribute.value(),
+            Err(MessageAttributeError::MissingValue(ref t)) if t == "Number"
+        ));
+
+        let attribute = SqsMessageAttribute {
+            string_value: Some("x".to_string()),
+            data_type: Some("Boolean".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            attribute.value(),
+            Err(MessageAttributeError::InvalidDataType(Some(_)))
+        ));
+    }
+
+    #[test]
+    fn deserialize_all_message_attributes() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Attributes {
+            #[serde(rename = "Attribute1")]
+            name: String,
+            #[serde(rename = "Attribute2")]
+            count: u32,
+            #[serde(rename = "Attribute3")]
+            data: Vec<u8>,
+            #[serde(default)]
+            missing: Option<String>,
+        }
+
+        let attributes: Attributes = example_message().deserialize_message_attributes().unwrap();
+        assert_eq!(
+            Attributes {
+                name: "AttributeValue1".to_string(),
+                count: 123,
+                data: b"1100".to_vec(),
+                missing: None,
+            },
+            attributes
+        );
+
+        let mut message = example_message();
+        message.message_attributes.insert(
+            "Meta".to_string(),
+            MessageAttributeValue::string(r#"{"tenant":"t1"}"#)
+                .with_custom_type("json")
+                .into(),
+        );
+        let meta: HashMap<String, Value> = message.deserialize_message_attributes().unwrap();
+        assert_eq!(json!({"tenant": "t1"}), meta["Meta"]);
+    }
+
+    #[test]
+    fn deserialize_system_attributes() {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Attributes {
+            approximate_receive_count: u32,
+            sent_timestamp: u64,
+            #[serde(default)]
+            message_group_id: Option<String>,
+        }
+
+        let attributes: Attributes = example_message().deserialize_attributes().unwrap();
+        assert_eq!(2, attributes.approximate_receive_count);
+        assert_eq!(1520621625029, attributes.sent_timestamp);
+        assert_eq!(None, attributes.message_group_id);
+    }
+}
@@ -0,0 +1,281 @@
+use super::{SqsMessage, SqsMessageAttribute};
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::{error, fmt};
+
+const STRING_TRANSPORT_TYPE: u8 = 1;
+const BINARY_TRANSPORT_TYPE: u8 = 2;
+const STRING_LIST_TRANSPORT_TYPE: u8 = 3;
+const BINARY_LIST_TRANSPORT_TYPE: u8 = 4;
+
+/// `Md5Mismatch` is returned when the digest sent by SQS doesn't match the message,
+/// or when a digest that SQS always sends is missing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Md5Mismatch {
+    /// `md5_of_body` doesn't match the body.
+    Body { expected: String, actual: String },
+    /// `md5_of_message_attributes` doesn't match the message attributes.
+    MessageAttributes { expected: String, actual: String },
+    /// The message has no `md5_of_body`.
+    MissingBodyDigest,
+    /// The message has attributes, but no `md5_of_message_attributes`.
+    MissingMessageAttributesDigest,
+}
+
+impl fmt::Display for Md5Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Md5Mismatch::Body { expected, actual } => write!(
+                f,
+                "MD5 of message body mismatch: expected {}, got {}",
+                expected, actual
+            ),
+            Md5Mismatch::MessageAttributes { expected, actual } => write!(
+                f,
+                "MD5 of message attributes mismatch: expected {}, got {}",
+                expected, actual
+            ),
+            Md5Mismatch::MissingBodyDigest => write!(f, "missing MD5 of message body"),
+            Md5Mismatch::MissingMessageAttributesDigest => {
+                write!(f, "missing MD5 of message attributes")
+            }
+        }
+    }
+}
+
+impl error::Error for Md5Mismatch {}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn update_length_prefixed(hasher: &mut Md5, value: &[u8]) {
+    hasher.update((value.len() as u32).to_be_bytes());
+    hasher.update(value);
+}
+
+/// Returns the hex encoded MD5 digest of a message body, like `md5_of_body`.
+pub fn md5_of_body(body: &str) -> String {
+    hex(&Md5::digest(body.as_bytes()))
+}
+
+/// Returns the hex encoded MD5 digest of message attributes, like `md5_of_message_attributes`.
+///
+/// Attributes are sorted by name and every name, data type and value is encoded
+/// with a 4 bytes big-endian length prefix, followed by a transport type byte before the value.
+pub fn md5_of_message_attributes(attributes: &HashMap<String, SqsMessageAttribute>) -> String {
+    let mut names: Vec<&String> = attributes.keys().collect();
+    names.sort();
+
+    let mut hasher = Md5::new();
+    for name in names {
+        let attribute = &attributes[name];
+        update_length_prefixed(&mut hasher, name.as_bytes());
+        update_length_prefixed(
+            &mut hasher,
+            attribute
+                .data_type
+                .as_deref()
+                .unwrap_or_default()
+                .as_bytes(),
+        );
+        if let Some(ref value) = attribute.string_value {
+            hasher.update([STRING_TRANSPORT_TYPE]);
+            update_length_prefixed(&mut hasher, value.as_bytes());
+        } else if let Some(ref value) = attribute.binary_value {
+            hasher.update([BINARY_TRANSPORT_TYPE]);
+            update_length_prefixed(&mut hasher, value);
+        } else if !attribute.string_list_values.is_empty() {
+            hasher.update([STRING_LIST_TRANSPORT_TYPE]);
+            for value in &attribute.string_list_values {
+                update_length_prefixed(&mut hasher, value.as_bytes());
+            }
+        } else if !attribute.binary_list_values.is_empty() {
+            hasher.update([BINARY_LIST_TRANSPORT_TYPE]);
+            for value in &attribute.binary_list_values {
+                update_length_prefixed(&mut hasher, value);
+            }
+        }
+    }
+    hex(&hasher.finalize())
+}
+
+impl SqsMessage {
+    /// Checks `md5_of_body` against the body. Messages without a digest fail the check,
+    /// since SQS sends one with every message.
+    pub fn verify_md5_of_body(&self) -> Result<(), Md5Mismatch> {
+        let expected = match self.md5_of_body {
+            Some(ref expected) => expected,
+            None => return Err(Md5Mismatch::MissingBodyDigest),
+        };
+        let actual = md5_of_body(self.body.as_deref().unwrap_or_default());
+        if expected.eq_ignore_ascii_case(&actual) {
+            Ok(())
+        } else {
+            Err(Md5Mismatch::Body {
+                expected: expected.clone(),
+                actual,
+            })
+        }
+    }
+
+    /// Checks `md5_of_message_attributes` against the message attributes.
+    /// Messages with attributes and without a digest fail the check, since SQS sends one
+    /// whenever a message has attributes.
+    pub fn verify_md5_of_message_attributes(&self) -> Result<(), Md5Mismatch> {
+        let expected = match self.md5_of_message_attributes {
+            Some(ref expected) => expected,
+            None if self.message_attributes.is_empty() => return Ok(()),
+            None => return Err(Md5Mismatch::MissingMessageAttributesDigest),
+        };
+        let actual = md5_of_message_attributes(&self.message_attributes);
+        if expected.eq_ignore_ascii_case(&actual) {
+            Ok(())
+        } else {
+            Err(Md5Mismatch::MessageAttributes {
+                expected: expected.clone(),
+                actual,
+            })
+        }
+    }
+
+    /// Checks both the body and the message attributes digests.
+    pub fn verify_md5(&self) -> Result<(), Md5Mismatch> {
+        self.verify_md5_of_body()?;
+        self.verify_md5_of_message_attributes()
+    }
+
+    /// Sets `md5_of_body` and `md5_of_message_attributes` from the message,
+    /// e.g. when building events for tests. The attributes digest is only set
+    /// when the message has attributes, like SQS does.
+    pub fn update_md5(&mut self) {
+        self.md5_of_body = Some(md5_of_body(self.body.as_deref().unwrap_or_default()));
+        self.md5_of_message_attributes = if self.message_attributes.is_empty() {
+            None
+        } else {
+            Some(md5_of_message_attributes(&self.message_attributes))
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{MessageAttributeValue, SqsEvent};
+    use super::*;
+
+    extern crate serde_json;
+
+    fn example_message() -> SqsMessage {
+        let data = include_bytes!("../fixtures/example-sqs-event.json");
+        let mut event: SqsEvent = serde_json::from_slice(data).unwrap();
+        event.records.remove(0)
+    }
+
+    #[test]
+    fn body_digest() {
+        assert_eq!(
+            "77a3cf88eba293be44f5fd5c61835a43",
+            md5_of_body("Message Body")
+        );
+        assert_eq!("d41d8cd98f00b204e9800998ecf8427e", md5_of_body(""));
+    }
+
+    #[test]
+    fn message_attributes_digest() {
+        let message = example_message();
+        assert_eq!(
+            "5e245f81e602ee5aa6641584635f9a43",
+            md5_of_message_attributes(&message.message_attributes)
+        );
+
+        let mut attributes = HashMap::new();
+        attributes.insert(
+            "tags".to_string(),
+            SqsMessageAttribute {
+                string_list_values: vec!["a".to_string(), "b".to_string()],
+                data_type: Some("String".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            "80fc67f40702107a63556558dd37716b",
+            md5_of_message_attributes(&attributes)
+        );
+    }
+
+    /// Digests returned by SQS, from the boto unit tests and the AWS CLI examples.
+    #[test]
+    fn digests_returned_by_sqs() {
+        assert_eq!(
+            "ce114e4501d2f4e2dcea3e17b546f339",
+            md5_of_body("This is a test")
+        );
+
+        let mut attributes = HashMap::new();
+        attributes.insert("Count".to_string(), MessageAttributeValue::number(1).into());
+        attributes.insert(
+            "Foo".to_string(),
+            MessageAttributeValue::string("Bar").into(),
+        );
+        assert_eq!(
+            "324758f82d026ac6ec5b31a3b192d1e3",
+            md5_of_message_attributes(&attributes)
+        );
+
+        // The AWS CLI example only shows the start and the end of the digest. The CLI sent
+        // the base64 encoding of `Hello, World!` as the binary value.
+        let mut attributes = HashMap::new();
+        attributes.insert(
+            "City".to_string(),
+            MessageAttributeValue::string("Any City").into(),
+        );
+        attributes.insert(
+            "Greeting".to_string(),
+            MessageAttributeValue::binary("SGVsbG8sIFdvcmxkIQ==").into(),
+        );
+        attributes.insert(
+            "Population".to_string(),
+            MessageAttributeValue::number(1250800).into(),
+        );
+        let digest = md5_of_message_attributes(&attributes);
+        assert!(digest.starts_with("00484c68"), "{}", digest);
+        assert!(digest.ends_with("59e48f06"), "{}", digest);
+    }
+
+    #[test]
+    fn verify_message_digests() {
+        let mut message = example_message();
+        assert_eq!(
+            Err(Md5Mismatch::Body {
+                expected: "fce0ea8dd236ccb3ed9b37dae260836f".to_string(),
+                actual: "77a3cf88eba293be44f5fd5c61835a43".to_string(),
+            }),
+            message.verify_md5()
+        );
+
+        message.update_md5();
+        assert_eq!(Ok(()), message.verify_md5());
+
+        message.message_attributes.insert(
+            "Attribute4".to_string(),
+            MessageAttributeValue::number(1).into(),
+        );
+        assert_eq!(Ok(()), message.verify_md5_of_body());
+        match message.verify_md5() {
+            Err(Md5Mismatch::MessageAttributes { .. }) => {}
+            not => panic!("expected MessageAttributes mismatch got {:?}", not),
+        }
+
+        message.md5_of_message_attributes = None;
+        assert_eq!(
+            Err(Md5Mismatch::MissingMessageAttributesDigest),
+            message.verify_md5_of_message_attributes()
+        );
+        message.message_attributes.clear();
+        assert_eq!(Ok(()), message.verify_md5_of_message_attributes());
+
+        message.md5_of_body = None;
+        message.body = Some("tampered".to_string());
+        assert_eq!(Err(Md5Mismatch::MissingBodyDigest), message.verify_md5());
+    }
+}
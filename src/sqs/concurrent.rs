@@ -0,0 +1,357 @@
+use super::system_attributes::MESSAGE_GROUP_ID;
+use super::{SqsBatchResponse, SqsEvent, SqsEventObj, SqsMessage, SqsMessageObj};
+use crate::streams::BatchEvent;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// `SqsRecord` is implemented by the messages that can be processed concurrently.
+pub trait SqsRecord {
+    /// Returns the message id, reported when the message fails.
+    fn item_identifier(&self) -> Option<&str>;
+
+    /// Returns the `MessageGroupId` of messages from FIFO queues.
+    fn message_group_id(&self) -> Option<&str>;
+}
+
+impl SqsRecord for SqsMessage {
+    fn item_identifier(&self) -> Option<&str> {
+        self.message_id.as_deref()
+    }
+
+    fn message_group_id(&self) -> Option<&str> {
+        self.attributes.get(MESSAGE_GROUP_ID).map(String::as_str)
+    }
+}
+
+impl<T: Serialize> SqsRecord for SqsMessageObj<T> {
+    fn item_identifier(&self) -> Option<&str> {
+        self.message_id.as_deref()
+    }
+
+    fn message_group_id(&self) -> Option<&str> {
+        self.attributes.get(MESSAGE_GROUP_ID).map(String::as_str)
+    }
+}
+
+/// A message group, with the position of its records in the batch.
+struct Group<'a, R> {
+    records: Vec<(usize, &'a R)>,
+    next: usize,
+}
+
+/// `ProcessRecords` is the future returned by `process_concurrently`.
+///
+/// Message groups run concurrently, up to the concurrency limit, while the records
+/// of a group run one after the other. When a record fails, the rest of its group
+/// is skipped and reported as failed too, so SQS delivers them again in order.
+/// Messages without a group, from standard queues, are groups of their own.
+pub struct ProcessRecords<'a, R, F, Fut> {
+    f: F,
+    limit: usize,
+    pending: VecDeque<Group<'a, R>>,
+    running: Vec<(Group<'a, R>, Pin<Box<Fut>>)>,
+    failures: Vec<(usize, Option<String>)>,
+}
+
+impl<'a, R, F, Fut> Unpin for ProcessRecords<'a, R, F, Fut> {}
+
+impl<'a, R, F, Fut> fmt::Debug for ProcessRecords<'a, R, F, Fut> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProcessRecords")
+            .field("limit", &self.limit)
+            .field("pending", &self.pending.len())
+            .field("running", &self.running.len())
+            .finish()
+    }
+}
+
+impl<'a, R, F, Fut, E> ProcessRecords<'a, R, F, Fut>
+where
+    R: SqsRecord,
+    F: FnMut(&'a R) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+{
+    fn new(records: &'a [R], limit: usize, f: F) -> Self {
+        let mut pending: VecDeque<Group<'a, R>> = VecDeque::new();
+        let mut groups: HashMap<&'a str, usize> = HashMap::new();
+        for (position, record) in records.iter().enumerate() {
+            let group_id = record.message_group_id();
+            let existing = group_id.and_then(|id| groups.get(id).copied());
+            match existing {
+                Some(index) => pending[index].records.push((position, record)),
+                None => {
+                    if let Some(id) = group_id {
+                        groups.insert(id, pending.len());
+                    }
+                    pending.push_back(Group {
+                        records: vec![(position, record)],
+                        next: 0,
+                    });
+                }
+            }
+        }
+        ProcessRecords {
+            f,
+            limit: limit.max(1),
+            pending,
+            running: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    /// Starts the next record of `group`, or drops the group when it's done.
+    fn start(&mut self, mut group: Group<'a, R>) {
+        if let Some(&(_, record)) = group.records.get(group.next) {
+            group.next += 1;
+            let future = Box::pin((self.f)(record));
+            self.running.push((group, future));
+        }
+    }
+
+    fn fail(&mut self, group: &Group<'a, R>) {
+        for &(position, record) in &group.records[group.next - 1..] {
+            self.failures
+                .push((position, record.item_identifier().map(String::from)));
+        }
+    }
+}
+
+impl<'a, R, F, Fut, E> Future for ProcessRecords<'a, R, F, Fut>
+where
+    R: SqsRecord,
+    F: FnMut(&'a R) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+{
+    type Output = SqsBatchResponse;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            while this.running.len() < this.limit {
+                match this.pending.pop_front() {
+                    Some(group) => this.start(group),
+                    None => break,
+                }
+            }
+            if this.running.is_empty() {
+                let mut failures = std::mem::take(&mut this.failures);
+                failures.sort_by_key(|&(position, _)| position);
+                return Poll::Ready(SqsEvent::batch_response(
+                    failures.into_iter().map(|(_, id)| id).collect(),
+                ));
+            }
+
+            let mut progress = false;
+            let mut index = 0;
+            while index < this.running.len() {
+                let result = match this.running[index].1.as_mut().poll(cx) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => {
+                        index += 1;
+                        continue;
+                    }
+                };
+                progress = true;
+                let (group, _) = this.running.swap_remove(index);
+                match result {
+                    Ok(()) => this.pending.push_front(group),
+                    Err(_) => this.fail(&group),
+                }
+            }
+            if !progress {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl SqsEvent {
+    /// Processes the messages concurrently with `f`, running up to `limit` message groups
+    /// at the same time, and returns the response with the messages that failed.
+    ///
+    /// Messages that share a `MessageGroupId` are processed in order, and the messages
+    /// after a failure in a group are not processed.
+    pub fn process_concurrently<'a, F, Fut, E>(
+        &'a self,
+        limit: usize,
+        f: F,
+    ) -> ProcessRecords<'a, SqsMessage, F, Fut>
+    where
+        F: FnMut(&'a SqsMessage) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        ProcessRecords::new(&self.records, limit, f)
+    }
+}
+
+impl<T: Serialize> SqsEventObj<T> {
+    /// Processes the messages concurrently with `f`, running up to `limit` message groups
+    /// at the same time, and returns the response with the messages that failed.
+    ///
+    /// Messages that share a `MessageGroupId` are processed in order, and the messages
+    /// after a failure in a group are not processed.
+    pub fn process_concurrently<'a, F, Fut, E>(
+        &'a self,
+        limit: usize,
+        f: F,
+    ) -> ProcessRecords<'a, SqsMessageObj<T>, F, Fut>
+    where
+        F: FnMut(&'a SqsMessageObj<T>) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        ProcessRecords::new(&self.records, limit, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::block_on;
+    use std::cell::{Cell, RefCell};
+
+    /// Stays pending for one poll, so other records get to run.
+    struct YieldOnce<T> {
+        yielded: bool,
+        output: Option<T>,
+    }
+
+    impl<T: Unpin> Future for YieldOnce<T> {
+        type Output = T;
+
+        fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<T> {
+            let this = self.get_mut();
+            if this.yielded {
+                Poll::Ready(this.output.take().expect("polled after completion"))
+            } else {
+                this.yielded = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    fn message(id: &str, group: Option<&str>) -> SqsMessage {
+        let mut message = SqsMessage {
+            message_id: Some(id.to_string()),
+            ..Default::default()
+        };
+        if let Some(group) = group {
+            message
+                .attributes
+                .insert(MESSAGE_GROUP_ID.to_string(), group.to_string());
+        }
+        message
+    }
+
+    fn failed_ids(response: &SqsBatchResponse) -> Vec<&str> {
+        response
+            .batch_item_failures
+            .iter()
+            .map(|f| f.item_identifier.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn fifo_groups_skip_after_failure() {
+        let event = SqsEvent {
+            records: vec![
+                message("a1", Some("a")),
+                message("b1", Some("b")),
+                message("a2", Some("a")),
+                message("b2", Some("b")),
+                message("a3", Some("a")),
+            ],
+        };
+        let processed = RefCell::new(Vec::new());
+        let response = block_on(event.process_concurrently(2, |message| {
+            let id = message.message_id.as_deref().unwrap();
+            processed.borrow_mut().push(id.to_string());
+            YieldOnce {
+                yielded: false,
+                output: Some(if id == "a2" { Err("failed") } else { Ok(()) }),
+            }
+        }));
+
+        assert_eq!(vec!["a2", "a3"], failed_ids(&response));
+        let processed = processed.into_inner();
+        assert!(!processed.contains(&"a3".to_string()));
+        let position = |id: &str| processed.iter().position(|p| p == id).unwrap();
+        assert!(position("a1") < position("a2"));
+        assert!(position("b1") < position("b2"));
+    }
+
+    #[test]
+    fn fifo_groups_with_malformed_attributes() {
+        let mut first = message("a1", Some("a"));
+        first
+            .attributes
+            .insert("SentTimestamp".to_string(), "not a timestamp".to_string());
+        let event = SqsEvent {
+            records: vec![first, message("a2", Some("a"))],
+        };
+        let processed = RefCell::new(Vec::new());
+        let response = block_on(event.process_concurrently(2, |message| {
+            processed
+                .borrow_mut()
+                .push(message.message_id.clone().unwrap());
+            std::future::ready(Err::<(), _>("failed"))
+        }));
+
+        assert_eq!(vec!["a1", "a2"], failed_ids(&response));
+        assert_eq!(vec!["a1".to_string()], processed.into_inner());
+    }
+
+    #[test]
+    fn standard_messages_respect_the_limit() {
+        let event = SqsEvent {
+            records: (0..6).map(|i| message(&i.to_string(), None)).collect(),
+        };
+        let running = Cell::new(0);
+        let max_running = Cell::new(0);
+
+        struct Tracked<'a> {
+            inner: YieldOnce<Result<(), ()>>,
+            running: &'a Cell<usize>,
+        }
+
+        impl<'a> Future for Tracked<'a> {
+            type Output = Result<(), ()>;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                let poll = Pin::new(&mut self.inner).poll(cx);
+                if poll.is_ready() {
+                    self.running.set(self.running.get() - 1);
+                }
+                poll
+            }
+        }
+
+        let response = block_on(event.process_concurrently(3, |message| {
+            running.set(running.get() + 1);
+            max_running.set(max_running.get().max(running.get()));
+            let fail = message.message_id.as_deref() == Some("4");
+            Tracked {
+                inner: YieldOnce {
+                    yielded: false,
+                    output: Some(if fail { Err(()) } else { Ok(()) }),
+                },
+                running: &running,
+            }
+        }));
+
+        assert_eq!(vec!["4"], failed_ids(&response));
+        assert_eq!(3, max_running.get());
+    }
+
+    #[test]
+    fn process_event_objects() {
+        let data = include_bytes!("../fixtures/example-sqs-event-obj.json");
+        let event: SqsEventObj<serde_json::Value> = serde_json::from_slice(data).unwrap();
+        let response =
+            block_on(event.process_concurrently(10, |_| std::future::ready(Err::<(), _>(()))));
+        assert_eq!(event.records.len(), response.batch_item_failures.len());
+    }
+}
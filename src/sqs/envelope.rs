@@ -0,0 +1,220 @@
+use super::SqsMessage;
+#[cfg(feature = "cloudwatch_events")]
+use crate::cloudwatch_events::CloudWatchEvent;
+#[cfg(feature = "sns")]
+use crate::sns::{SnsMessage, SnsMessageObj};
+#[cfg(any(feature = "sns", feature = "cloudwatch_events"))]
+use serde::de::DeserializeOwned;
+#[cfg(any(feature = "sns", feature = "cloudwatch_events"))]
+use serde::Serialize;
+use std::{error, fmt};
+
+/// `EnvelopeError` is returned when the body of an SQS message is not the expected envelope.
+#[derive(Debug)]
+pub enum EnvelopeError {
+    /// The message has no body.
+    MissingBody,
+    /// The body is not an envelope of the given kind, e.g. `SNS notification`.
+    InvalidEnvelope(&'static str, serde_json::Error),
+    /// The body is an envelope, but its content could not be deserialized.
+    InvalidContent(&'static str, serde_json::Error),
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvelopeError::MissingBody => write!(f, "the SQS message has no body"),
+            EnvelopeError::InvalidEnvelope(kind, e) => {
+                write!(f, "the SQS message body is not an {}: {}", kind, e)
+            }
+            EnvelopeError::InvalidContent(kind, e) => {
+                write!(f, "invalid content in {}: {}", kind, e)
+            }
+        }
+    }
+}
+
+impl error::Error for EnvelopeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            EnvelopeError::MissingBody => None,
+            EnvelopeError::InvalidEnvelope(_, e) | EnvelopeError::InvalidContent(_, e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "sns")]
+const SNS_NOTIFICATION: &str = "SNS notification";
+#[cfg(feature = "cloudwatch_events")]
+const CLOUDWATCH_EVENT: &str = "EventBridge event";
+
+impl SqsMessage {
+    #[cfg(any(feature = "sns", feature = "cloudwatch_events"))]
+    fn envelope<T: DeserializeOwned>(&self, kind: &'static str) -> Result<T, EnvelopeError> {
+        let body = self.body.as_deref().ok_or(EnvelopeError::MissingBody)?;
+        serde_json::from_str(body).map_err(|e| EnvelopeError::InvalidEnvelope(kind, e))
+    }
+
+    /// Deserializes the SNS notification delivered to a queue subscribed to a topic
+    /// without raw message delivery.
+    #[cfg(feature = "sns")]
+    pub fn sns_message(&self) -> Result<SnsMessage, EnvelopeError> {
+        self.envelope(SNS_NOTIFICATION)
+    }
+
+    /// Deserializes the SNS notification, and its message into a `T`.
+    #[cfg(feature = "sns")]
+    pub fn sns_message_obj<T>(&self) -> Result<SnsMessageObj<T>, EnvelopeError>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let sns = self.sns_message()?;
+        let message = serde_json::from_str(&sns.message)
+            .map_err(|e| EnvelopeError::InvalidContent(SNS_NOTIFICATION, e))?;
+        Ok(SnsMessageObj {
+            sns_message_type: sns.sns_message_type,
+            message_id: sns.message_id,
+            topic_arn: sns.topic_arn,
+            subject: sns.subject,
+            timestamp: sns.timestamp,
+            signature_version: sns.signature_version,
+            signature: sns.signature,
+            signing_cert_url: sns.signing_cert_url,
+            unsubscribe_url: sns.unsubscribe_url,
+            message,
+            message_attributes: sns.message_attributes,
+        })
+    }
+
+    /// Deserializes the EventBridge event delivered to a queue target, and its detail into a `T`.
+    #[cfg(feature = "cloudwatch_events")]
+    pub fn cloudwatch_event<T>(&self) -> Result<CloudWatchEvent<T>, EnvelopeError>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let event: CloudWatchEvent = self.envelope(CLOUDWATCH_EVENT)?;
+        let detail = match event.detail {
+            Some(detail) => Some(
+                serde_json::from_value(detail)
+                    .map_err(|e| EnvelopeError::InvalidContent(CLOUDWATCH_EVENT, e))?,
+            ),
+            None => None,
+        };
+        Ok(CloudWatchEvent {
+            version: event.version,
+            id: event.id,
+            detail_type: event.detail_type,
+            source: event.source,
+            account_id: event.account_id,
+            time: event.time,
+            region: event.region,
+            resources: event.resources,
+            detail,
+        })
+    }
+}
+
+#[cfg(all(test, any(feature = "sns", feature = "cloudwatch_events")))]
+mod test {
+    use super::*;
+
+    extern crate serde_json;
+
+    fn message(body: &str) -> SqsMessage {
+        SqsMessage {
+            body: Some(body.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Order {
+        id: u32,
+    }
+
+    #[test]
+    #[cfg(feature = "sns")]
+    fn unwrap_sns_notifications() {
+        let body = json!({
+            "Type": "Notification",
+            "MessageId": "82833b5c-8d5d-56d0-b0e1-7511f8253eb8",
+            "TopicArn": "arn:aws:sns:us-east-1:123456789012:orders",
+            "Subject": null,
+            "Message": "{\"id\":42}",
+            "Timestamp": "2015-08-18T18:02:32.111Z",
+            "SignatureVersion": "1",
+            "Signature": "EXAMPLE",
+            "SigningCertURL": "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-example.pem",
+            "UnsubscribeURL": "https://sns.us-east-1.amazonaws.com/?Action=Unsubscribe",
+            "MessageAttributes": {
+                "tenant": {"Type": "String", "Value": "t1"}
+            }
+        })
+        .to_string();
+        let message = message(&body);
+
+        let sns = message.sns_message().unwrap();
+        assert_eq!("Notification", sns.sns_message_type);
+        assert_eq!("{\"id\":42}", sns.message);
+        assert_eq!("t1", sns.message_attributes["tenant"].value);
+
+        let sns = message.sns_message_obj::<Order>().unwrap();
+        assert_eq!(Order { id: 42 }, sns.message);
+        assert_eq!("arn:aws:sns:us-east-1:123456789012:orders", sns.topic_arn);
+    }
+
+    #[test]
+    #[cfg(feature = "sns")]
+    fn invalid_sns_notifications() {
+        match message("Message Body").sns_message() {
+            Err(EnvelopeError::InvalidEnvelope(SNS_NOTIFICATION, _)) => {}
+            not => panic!("expected InvalidEnvelope got {:?}", not),
+        }
+        match SqsMessage::default().sns_message() {
+            Err(EnvelopeError::MissingBody) => {}
+            not => panic!("expected MissingBody got {:?}", not),
+        }
+
+        let data = include_bytes!("../fixtures/example-sns-event.json");
+        let event: crate::sns::SnsEvent = serde_json::from_slice(data).unwrap();
+        let body = serde_json::to_string(&event.records[0].sns).unwrap();
+        let err = message(&body).sns_message_obj::<Order>().unwrap_err();
+        match err {
+            EnvelopeError::InvalidContent(SNS_NOTIFICATION, _) => {}
+            ref not => panic!("expected InvalidContent got {:?}", not),
+        }
+        assert!(err
+            .to_string()
+            .starts_with("invalid content in SNS notification"));
+    }
+
+    #[test]
+    #[cfg(feature = "cloudwatch_events")]
+    fn unwrap_eventbridge_events() {
+        let body = json!({
+            "version": "0",
+            "id": "6a7e8feb-b491-4cf7-a9f1-bf3703467718",
+            "detail-type": "OrderCreated",
+            "source": "com.example.orders",
+            "account": "123456789012",
+            "time": "2017-12-22T18:43:48Z",
+            "region": "us-east-1",
+            "resources": [],
+            "detail": {"id": 42}
+        })
+        .to_string();
+
+        let event = message(&body).cloudwatch_event::<Order>().unwrap();
+        assert_eq!(Some("OrderCreated"), event.detail_type.as_deref());
+        assert_eq!(Some(Order { id: 42 }), event.detail);
+
+        match message(&body).cloudwatch_event::<Vec<String>>() {
+            Err(EnvelopeError::InvalidContent(CLOUDWATCH_EVENT, _)) => {}
+            not => panic!("expected InvalidContent got {:?}", not),
+        }
+        match message("{}").cloudwatch_event::<Order>() {
+            Err(EnvelopeError::InvalidEnvelope(CLOUDWATCH_EVENT, _)) => {}
+            not => panic!("expected InvalidEnvelope got {:?}", not),
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use super::{SqsMessage, SqsMessageObj};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::string::FromUtf8Error;
+use std::task::{Context, Poll};
+use std::{error, fmt};
+
+/// The class name that the SQS Extended Client Library writes before the pointer.
+pub const PAYLOAD_S3_POINTER_CLASS: &str = "software.amazon.payloadoffloading.PayloadS3Pointer";
+
+/// The message attribute with the size of the payload stored in S3.
+pub const LARGE_PAYLOAD_SIZE_ATTRIBUTE: &str = "SQSLargePayloadSize";
+
+/// The name of the size attribute used by older versions of the Extended Client Library.
+const LEGACY_LARGE_PAYLOAD_SIZE_ATTRIBUTE: &str = "ExtendedPayloadSize";
+
+/// `PayloadS3Pointer` is the location of a message body stored in S3 by the SQS Extended Client Library.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadS3Pointer {
+    pub s3_bucket_name: String,
+    pub s3_key: String,
+}
+
+impl PayloadS3Pointer {
+    /// Parses a message body, returning `None` when the body is not a payload pointer.
+    pub fn from_body(body: &str) -> Result<Option<Self>, serde_json::Error> {
+        let body = body.trim();
+        if !body.starts_with('[') || !body.contains(PAYLOAD_S3_POINTER_CLASS) {
+            return Ok(None);
+        }
+        let (class, pointer): (String, PayloadS3Pointer) = serde_json::from_str(body)?;
+        if class == PAYLOAD_S3_POINTER_CLASS {
+            Ok(Some(pointer))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the message body that points to this payload.
+    pub fn to_body(&self) -> String {
+        serde_json::to_string(&(PAYLOAD_S3_POINTER_CLASS, self))
+            .expect("payload pointers serialize to JSON")
+    }
+}
+
+/// `PayloadFetcher` downloads the payloads that pointers refer to, usually from S3.
+pub trait PayloadFetcher {
+    type Error;
+    type Future: Future<Output = Result<Vec<u8>, Self::Error>>;
+
+    /// Fetches the payload stored at `pointer`.
+    fn fetch(&self, pointer: &PayloadS3Pointer) -> Self::Future;
+}
+
+/// `PayloadNotFound` is returned by `InMemoryPayloadStore` for unknown pointers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PayloadNotFound(pub PayloadS3Pointer);
+
+impl fmt::Display for PayloadNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "payload not found: s3://{}/{}",
+            self.0.s3_bucket_name, self.0.s3_key
+        )
+    }
+}
+
+impl error::Error for PayloadNotFound {}
+
+/// `InMemoryPayloadStore` is a `PayloadFetcher` that keeps payloads in memory, e.g. for tests.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryPayloadStore {
+    payloads: HashMap<PayloadS3Pointer, Vec<u8>>,
+}
+
+impl InMemoryPayloadStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a payload, returning its pointer.
+    pub fn insert<P: Into<Vec<u8>>>(
+        &mut self,
+        bucket: &str,
+        key: &str,
+        payload: P,
+    ) -> PayloadS3Pointer {
+        let pointer = PayloadS3Pointer {
+            s3_bucket_name: bucket.to_string(),
+            s3_key: key.to_string(),
+        };
+        self.payloads.insert(pointer.clone(), payload.into());
+        pointer
+    }
+}
+
+impl PayloadFetcher for InMemoryPayloadStore {
+    type Error = PayloadNotFound;
+    type Future = future::Ready<Result<Vec<u8>, PayloadNotFound>>;
+
+    fn fetch(&self, pointer: &PayloadS3Pointer) -> Self::Future {
+        future::ready(
+            self.payloads
+                .get(pointer)
+                .cloned()
+                .ok_or_else(|| PayloadNotFound(pointer.clone())),
+        )
+    }
+}
+
+/// `PayloadError` is returned when the payload of a message can't be resolved.
+#[derive(Debug)]
+pub enum PayloadError<E> {
+    /// The body looks like a payload pointer but is not valid.
+    InvalidPointer(serde_json::Error),
+    /// The payload could not be fetched.
+    Fetch(E),
+    /// The payload is not valid UTF-8.
+    InvalidUtf8(FromUtf8Error),
+}
+
+impl<E: fmt::Display> fmt::Display for PayloadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PayloadError::InvalidPointer(e) => write!(f, "invalid payload pointer: {}", e),
+            PayloadError::Fetch(e) => write!(f, "failed to fetch payload: {}", e),
+            PayloadError::InvalidUtf8(e) => write!(f, "invalid payload: {}", e),
+        }
+    }
+}
+
+impl<E> error::Error for PayloadError<E>
+where
+    E: error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PayloadError::InvalidPointer(e) => Some(e),
+            PayloadError::Fetch(e) => Some(e),
+            PayloadError::InvalidUtf8(e) => Some(e),
+        }
+    }
+}
+
+/// `ResolvePayload` is the future returned by `SqsMessage::resolve_payload`.
+///
+/// It resolves to `true` when the body was replaced by the payload,
+/// and to `false` when the message was not a payload pointer.
+pub struct ResolvePayload<'a, F> {
+    message: &'a mut SqsMessage,
+    state: Option<Result<Pin<Box<F>>, serde_json::Error>>,
+}
+
+impl<'a, F> fmt::Debug for ResolvePayload<'a, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResolvePayload").finish()
+    }
+}
+
+impl<'a, F, E> Future for ResolvePayload<'a, F>
+where
+    F: Future<Output = Result<Vec<u8>, E>>,
+{
+    type Output = Result<bool, PayloadError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let payload = match this.state {
+            None => return Poll::Ready(Ok(false)),
+            Some(Err(_)) => match this.state.take() {
+                Some(Err(e)) => return Poll::Ready(Err(PayloadError::InvalidPointer(e))),
+                _ => unreachable!(),
+            },
+            Some(Ok(ref mut fetch)) => match fetch.as_mut().poll(cx) {
+                Poll::Ready(payload) => payload,
+                Poll::Pending => return Poll::Pending,
+            },
+        };
+        this.state = None;
+        let payload = payload.map_err(PayloadError::Fetch)?;
+        let body = String::from_utf8(payload).map_err(PayloadError::InvalidUtf8)?;
+        this.message.body = Some(body);
+        Poll::Ready(Ok(true))
+    }
+}
+
+impl SqsMessage {
+    /// Returns the S3 pointer of messages sent with the SQS Extended Client Library.
+    pub fn payload_pointer(&self) -> Result<Option<PayloadS3Pointer>, serde_json::Error> {
+        match self.body {
+            Some(ref body) => PayloadS3Pointer::from_body(body),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the size, in bytes, of the payload stored in S3.
+    pub fn large_payload_size(&self) -> Option<u64> {
+        self.message_attributes
+            .get(LARGE_PAYLOAD_SIZE_ATTRIBUTE)
+            .or_else(|| {
+                self.message_attributes
+                    .get(LEGACY_LARGE_PAYLOAD_SIZE_ATTRIBUTE)
+            })
+            .and_then(|attribute| attribute.string_value.as_deref())
+            .and_then(|size| size.trim().parse().ok())
+    }
+
+    /// Replaces a payload pointer body with the payload fetched by `fetcher`.
+    /// Messages that are not payload pointers are left untouched.
+    pub fn resolve_payload<P: PayloadFetcher>(
+        &mut self,
+        fetcher: &P,
+    ) -> ResolvePayload<'_, P::Future> {
+        let state = match self.payload_pointer() {
+            Ok(Some(pointer)) => Some(Ok(Box::pin(fetcher.fetch(&pointer)))),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        };
+        ResolvePayload {
+            message: self,
+            state,
+        }
+    }
+
+    /// Deserializes the body into a `T`, e.g. after resolving its payload.
+    pub fn into_message_obj<T>(self) -> Result<SqsMessageObj<T>, serde_json::Error>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let body = serde_json::from_str(self.body.as_deref().unwrap_or_default())?;
+        Ok(SqsMessageObj {
+            message_id: self.message_id,
+            receipt_handle: self.receipt_handle,
+            body,
+            md5_of_body: self.md5_of_body,
+            md5_of_message_attributes: self.md5_of_message_attributes,
+            attributes: self.attributes,
+            message_attributes: self.message_attributes,
+            event_source_arn: self.event_source_arn,
+            event_source: self.event_source,
+            aws_region: self.aws_region,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{MessageAttributeValue, SqsEvent};
+    use super::*;
+    use crate::test_util::block_on;
+
+    extern crate serde_json;
+
+    fn pointer_message(pointer: &PayloadS3Pointer, size: usize) -> SqsMessage {
+        let mut message = SqsMessage {
+            message_id: Some("059f36b4-87a3-44ab-83d2-661975830a7d".to_string()),
+            body: Some(pointer.to_body()),
+            ..Default::default()
+        };
+        message.message_attributes.insert(
+            LARGE_PAYLOAD_SIZE_ATTRIBUTE.to_string(),
+            MessageAttributeValue::number(size).into(),
+        );
+        message
+    }
+
+    #[test]
+    fn parse_payload_pointers() {
+        let body = r#"["software.amazon.payloadoffloading.PayloadS3Pointer",{"s3BucketName":"large-payloads","s3Key":"8a3f7a4e-2e31-4a5c-9d0b-7d5e0bbf4b1c"}]"#;
+        let pointer = PayloadS3Pointer::from_body(body).unwrap().unwrap();
+        assert_eq!("large-payloads", pointer.s3_bucket_name);
+        assert_eq!("8a3f7a4e-2e31-4a5c-9d0b-7d5e0bbf4b1c", pointer.s3_key);
+        assert_eq!(body, pointer.to_body());
+
+        assert_eq!(None, PayloadS3Pointer::from_body("Message Body").unwrap());
+        assert_eq!(None, PayloadS3Pointer::from_body(r#"["a", "b"]"#).unwrap());
+        assert!(PayloadS3Pointer::from_body(
+            r#"["software.amazon.payloadoffloading.PayloadS3Pointer", {}]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn resolve_payloads() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Order {
+            id: u32,
+        }
+
+        let mut store = InMemoryPayloadStore::new();
+        let pointer = store.insert("large-payloads", "order", r#"{"id":42}"#);
+        let mut message = pointer_message(&pointer, 9);
+        assert_eq!(Some(pointer.clone()), message.payload_pointer().unwrap());
+        assert_eq!(Some(9), message.large_payload_size());
+
+        assert!(block_on(message.resolve_payload(&store)).unwrap());
+        assert_eq!(Some(r#"{"id":42}"#), message.body.as_deref());
+        assert!(!block_on(message.resolve_payload(&store)).unwrap());
+
+        let message = message.into_message_obj::<Order>().unwrap();
+        assert_eq!(Order { id: 42 }, message.body);
+    }
+
+    #[test]
+    fn resolve_payload_errors() {
+        let store = InMemoryPayloadStore::new();
+        let pointer = PayloadS3Pointer {
+            s3_bucket_name: "large-payloads".to_string(),
+            s3_key: "missing".to_string(),
+        };
+        let mut message = pointer_message(&pointer, 1);
+        match block_on(message.resolve_payload(&store)) {
+            Err(PayloadError::Fetch(PayloadNotFound(p))) => assert_eq!(pointer, p),
+            not => panic!("expected PayloadError::Fetch got {:?}", not),
+        }
+
+        let data = include_bytes!("../fixtures/example-sqs-event.json");
+        let mut event: SqsEvent = serde_json::from_slice(data).unwrap();
+        let message = &mut event.records[0];
+        assert_eq!(None, message.large_payload_size());
+        assert!(!block_on(message.resolve_payload(&store)).unwrap());
+        assert_eq!(Some("Message Body"), message.body.as_deref());
+    }
+}
@@ -3,6 +3,19 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+mod attributes;
+pub use self::attributes::*;
+mod checksum;
+pub use self::checksum::*;
+mod concurrent;
+pub use self::concurrent::{ProcessRecords, SqsRecord};
+mod envelope;
+pub use self::envelope::EnvelopeError;
+mod extended;
+pub use self::extended::*;
+mod system_attributes;
+pub use self::system_attributes::*;
+
 /// The Event sent to Lambda from SQS. Contains 1 or more individual SQS Messages
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,239 @@
+use super::{SqsMessage, SqsMessageObj};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::{error, fmt};
+
+const APPROXIMATE_RECEIVE_COUNT: &str = "ApproximateReceiveCount";
+const SENT_TIMESTAMP: &str = "SentTimestamp";
+const SENDER_ID: &str = "SenderId";
+const APPROXIMATE_FIRST_RECEIVE_TIMESTAMP: &str = "ApproximateFirstReceiveTimestamp";
+const SEQUENCE_NUMBER: &str = "SequenceNumber";
+pub(super) const MESSAGE_GROUP_ID: &str = "MessageGroupId";
+const MESSAGE_DEDUPLICATION_ID: &str = "MessageDeduplicationId";
+const AWS_TRACE_HEADER: &str = "AWSTraceHeader";
+const DEAD_LETTER_QUEUE_SOURCE_ARN: &str = "DeadLetterQueueSourceArn";
+
+/// `SystemAttributeError` is returned when a system attribute can't be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SystemAttributeError {
+    pub name: String,
+    pub value: String,
+}
+
+impl fmt::Display for SystemAttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid value for system attribute {}: {}",
+            self.name, self.value
+        )
+    }
+}
+
+impl error::Error for SystemAttributeError {}
+
+/// `SqsSystemAttributes` is the typed version of the `attributes` of an `SqsMessage`.
+///
+/// Timestamps are sent by SQS as milliseconds since the epoch. Keys that are not
+/// known system attributes are kept in `other`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SqsSystemAttributes {
+    pub approximate_receive_count: Option<u32>,
+    pub sent_timestamp: Option<DateTime<Utc>>,
+    pub sender_id: Option<String>,
+    pub approximate_first_receive_timestamp: Option<DateTime<Utc>>,
+    /// The sequence number of messages in FIFO queues.
+    pub sequence_number: Option<u128>,
+    /// The message group of messages in FIFO queues.
+    pub message_group_id: Option<String>,
+    /// The deduplication id of messages in FIFO queues.
+    pub message_deduplication_id: Option<String>,
+    /// The X-Ray trace header.
+    pub aws_trace_header: Option<String>,
+    /// The ARN of the source queue of messages moved to a dead-letter queue.
+    pub dead_letter_queue_source_arn: Option<String>,
+    pub other: HashMap<String, String>,
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, SystemAttributeError> {
+    value.trim().parse().map_err(|_| SystemAttributeError {
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+fn parse_milliseconds(name: &str, value: &str) -> Result<DateTime<Utc>, SystemAttributeError> {
+    let millis: i64 = parse(name, value)?;
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| SystemAttributeError {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+}
+
+impl SqsSystemAttributes {
+    /// Parses the `attributes` map of an SQS message.
+    pub fn from_map(attributes: &HashMap<String, String>) -> Result<Self, SystemAttributeError> {
+        let mut parsed = SqsSystemAttributes::default();
+        for (name, value) in attributes {
+            match name.as_str() {
+                APPROXIMATE_RECEIVE_COUNT => {
+                    parsed.approximate_receive_count = Some(parse(name, value)?)
+                }
+                SENT_TIMESTAMP => parsed.sent_timestamp = Some(parse_milliseconds(name, value)?),
+                SENDER_ID => parsed.sender_id = Some(value.clone()),
+                APPROXIMATE_FIRST_RECEIVE_TIMESTAMP => {
+                    parsed.approximate_first_receive_timestamp =
+                        Some(parse_milliseconds(name, value)?)
+                }
+                SEQUENCE_NUMBER => parsed.sequence_number = Some(parse(name, value)?),
+                MESSAGE_GROUP_ID => parsed.message_group_id = Some(value.clone()),
+                MESSAGE_DEDUPLICATION_ID => parsed.message_deduplication_id = Some(value.clone()),
+                AWS_TRACE_HEADER => parsed.aws_trace_header = Some(value.clone()),
+                DEAD_LETTER_QUEUE_SOURCE_ARN => {
+                    parsed.dead_letter_queue_source_arn = Some(value.clone())
+                }
+                _ => {
+                    parsed.other.insert(name.clone(), value.clone());
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Returns true when the message comes from a FIFO queue.
+    pub fn is_fifo(&self) -> bool {
+        self.message_group_id.is_some()
+    }
+}
+
+impl From<SqsSystemAttributes> for HashMap<String, String> {
+    fn from(attributes: SqsSystemAttributes) -> Self {
+        let mut map = attributes.other;
+        let millis = |t: DateTime<Utc>| t.timestamp_millis().to_string();
+        let known = [
+            (
+                APPROXIMATE_RECEIVE_COUNT,
+                attributes.approximate_receive_count.map(|c| c.to_string()),
+            ),
+            (SENT_TIMESTAMP, attributes.sent_timestamp.map(millis)),
+            (SENDER_ID, attributes.sender_id),
+            (
+                APPROXIMATE_FIRST_RECEIVE_TIMESTAMP,
+                attributes.approximate_first_receive_timestamp.map(millis),
+            ),
+            (
+                SEQUENCE_NUMBER,
+                attributes.sequence_number.map(|n| n.to_string()),
+            ),
+            (MESSAGE_GROUP_ID, attributes.message_group_id),
+            (
+                MESSAGE_DEDUPLICATION_ID,
+                attributes.message_deduplication_id,
+            ),
+            (AWS_TRACE_HEADER, attributes.aws_trace_header),
+            (
+                DEAD_LETTER_QUEUE_SOURCE_ARN,
+                attributes.dead_letter_queue_source_arn,
+            ),
+        ];
+        for (name, value) in known {
+            if let Some(value) = value {
+                map.insert(name.to_string(), value);
+            }
+        }
+        map
+    }
+}
+
+impl SqsMessage {
+    /// Parses the system attributes of the message.
+    pub fn system_attributes(&self) -> Result<SqsSystemAttributes, SystemAttributeError> {
+        SqsSystemAttributes::from_map(&self.attributes)
+    }
+}
+
+impl<T: Serialize> SqsMessageObj<T> {
+    /// Parses the system attributes of the message.
+    pub fn system_attributes(&self) -> Result<SqsSystemAttributes, SystemAttributeError> {
+        SqsSystemAttributes::from_map(&self.attributes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::SqsEvent;
+    use super::*;
+
+    extern crate serde_json;
+
+    #[test]
+    fn example_system_attributes() {
+        let data = include_bytes!("../fixtures/example-sqs-event.json");
+        let event: SqsEvent = serde_json::from_slice(data).unwrap();
+        let attributes = event.records[0].system_attributes().unwrap();
+
+        assert_eq!(Some(2), attributes.approximate_receive_count);
+        assert_eq!(
+            Some(Utc.timestamp_millis_opt(1520621625029).unwrap()),
+            attributes.sent_timestamp
+        );
+        assert_eq!(
+            Some("AROAIWPX5BD2BHG722MW4:sender"),
+            attributes.sender_id.as_deref()
+        );
+        assert!(attributes.approximate_first_receive_timestamp.is_some());
+        assert!(!attributes.is_fifo());
+        assert!(attributes.other.is_empty());
+
+        let map: HashMap<String, String> = attributes.into();
+        assert_eq!(event.records[0].attributes, map);
+    }
+
+    #[test]
+    fn fifo_system_attributes() {
+        let mut map = HashMap::new();
+        map.insert("MessageGroupId".to_string(), "group-1".to_string());
+        map.insert("MessageDeduplicationId".to_string(), "dedup".to_string());
+        map.insert(
+            "SequenceNumber".to_string(),
+            "18849496460467696128".to_string(),
+        );
+        map.insert(
+            "AWSTraceHeader".to_string(),
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Sampled=1".to_string(),
+        );
+        map.insert("Custom".to_string(), "value".to_string());
+
+        let attributes = SqsSystemAttributes::from_map(&map).unwrap();
+        assert!(attributes.is_fifo());
+        assert_eq!(Some("group-1"), attributes.message_group_id.as_deref());
+        assert_eq!(
+            Some("dedup"),
+            attributes.message_deduplication_id.as_deref()
+        );
+        assert_eq!(Some(18849496460467696128), attributes.sequence_number);
+        assert!(attributes.aws_trace_header.is_some());
+        assert_eq!(
+            Some("value"),
+            attributes.other.get("Custom").map(|v| v.as_str())
+        );
+        assert_eq!(map, HashMap::from(attributes));
+    }
+
+    #[test]
+    fn invalid_system_attributes() {
+        let mut map = HashMap::new();
+        map.insert("SentTimestamp".to_string(), "yesterday".to_string());
+        assert_eq!(
+            Err(SystemAttributeError {
+                name: "SentTimestamp".to_string(),
+                value: "yesterday".to_string(),
+            }),
+            SqsSystemAttributes::from_map(&map)
+        );
+    }
+}
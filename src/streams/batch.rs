@@ -0,0 +1,204 @@
+#[cfg(feature = "dynamodb")]
+use super::{DynamoDbBatchItemFailure, DynamoDbEventResponse};
+#[cfg(feature = "kinesis")]
+use super::{KinesisBatchItemFailure, KinesisEventResponse};
+#[cfg(feature = "dynamodb")]
+use crate::dynamodb;
+#[cfg(feature = "kinesis")]
+use crate::kinesis::{KinesisEvent, KinesisEventRecord};
+#[cfg(feature = "sqs")]
+use crate::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent, SqsMessage};
+
+/// `BatchEvent` is implemented by events whose records can be reported as partial batch failures.
+///
+/// `process_records` runs a function over every record and builds the response
+/// with the identifiers of the records that failed.
+pub trait BatchEvent {
+    type Record;
+    type Response;
+
+    /// Ordered sources, like Kinesis and DynamoDB streams, retry every record
+    /// after the first failure, so processing stops at that record.
+    const ORDERED: bool;
+
+    /// Returns the records in the event.
+    fn records(&self) -> &[Self::Record];
+
+    /// Returns the identifier reported for a failed record:
+    /// the message id for SQS and the sequence number for streams.
+    fn item_identifier(record: &Self::Record) -> Option<String>;
+
+    /// Builds the response reporting the failed identifiers.
+    fn batch_response(failures: Vec<Option<String>>) -> Self::Response;
+
+    /// Runs `f` on every record, returning the response with the records that failed.
+    fn process_records<F, E>(&self, mut f: F) -> Self::Response
+    where
+        F: FnMut(&Self::Record) -> Result<(), E>,
+    {
+        let mut failures = Vec::new();
+        for record in self.records() {
+            if f(record).is_err() {
+                failures.push(Self::item_identifier(record));
+                if Self::ORDERED {
+                    break;
+                }
+            }
+        }
+        Self::batch_response(failures)
+    }
+}
+
+#[cfg(feature = "sqs")]
+impl BatchEvent for SqsEvent {
+    type Record = SqsMessage;
+    type Response = SqsBatchResponse;
+
+    const ORDERED: bool = false;
+
+    fn records(&self) -> &[SqsMessage] {
+        &self.records
+    }
+
+    fn item_identifier(record: &SqsMessage) -> Option<String> {
+        record.message_id.clone()
+    }
+
+    fn batch_response(failures: Vec<Option<String>>) -> SqsBatchResponse {
+        SqsBatchResponse {
+            batch_item_failures: failures
+                .into_iter()
+                .map(|id| BatchItemFailure {
+                    item_identifier: id.unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "kinesis")]
+impl BatchEvent for KinesisEvent {
+    type Record = KinesisEventRecord;
+    type Response = KinesisEventResponse;
+
+    const ORDERED: bool = true;
+
+    fn records(&self) -> &[KinesisEventRecord] {
+        &self.records
+    }
+
+    fn item_identifier(record: &KinesisEventRecord) -> Option<String> {
+        record.kinesis.sequence_number.clone()
+    }
+
+    fn batch_response(failures: Vec<Option<String>>) -> KinesisEventResponse {
+        KinesisEventResponse {
+            batch_item_failures: failures
+                .into_iter()
+                .map(|item_identifier| KinesisBatchItemFailure { item_identifier })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "dynamodb")]
+impl BatchEvent for dynamodb::Event {
+    type Record = dynamodb::EventRecord;
+    type Response = DynamoDbEventResponse;
+
+    const ORDERED: bool = true;
+
+    fn records(&self) -> &[dynamodb::EventRecord] {
+        &self.records
+    }
+
+    fn item_identifier(record: &dynamodb::EventRecord) -> Option<String> {
+        record.change.sequence_number.clone()
+    }
+
+    fn batch_response(failures: Vec<Option<String>>) -> DynamoDbEventResponse {
+        DynamoDbEventResponse {
+            batch_item_failures: failures
+                .into_iter()
+                .map(|item_identifier| DynamoDbBatchItemFailure { item_identifier })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "sqs", feature = "kinesis", feature = "dynamodb")))]
+mod test {
+    use super::*;
+
+    extern crate serde_json;
+
+    #[test]
+    #[cfg(feature = "sqs")]
+    fn sqs_reports_every_failure() {
+        let mut event = SqsEvent::default();
+        for id in &["a", "b", "c"] {
+            event.records.push(SqsMessage {
+                message_id: Some(id.to_string()),
+                ..Default::default()
+            });
+        }
+
+        let response = event.process_records(|message| match message.message_id.as_deref() {
+            Some("b") => Ok(()),
+            _ => Err("failed"),
+        });
+        let failed: Vec<&str> = response
+            .batch_item_failures
+            .iter()
+            .map(|f| f.item_identifier.as_str())
+            .collect();
+        assert_eq!(vec!["a", "c"], failed);
+
+        let response = event.process_records(|_| Ok::<(), ()>(()));
+        assert!(response.batch_item_failures.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "kinesis")]
+    fn kinesis_stops_at_first_failure() {
+        let data = include_bytes!("../fixtures/example-kinesis-event.json");
+        let event: KinesisEvent = serde_json::from_slice(data).unwrap();
+
+        let mut processed = 0;
+        let response = event.process_records(|_| {
+            processed += 1;
+            Err("failed")
+        });
+        assert_eq!(1, processed);
+        assert_eq!(
+            vec![KinesisBatchItemFailure {
+                item_identifier: event.records[0].kinesis.sequence_number.clone(),
+            }],
+            response.batch_item_failures
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "dynamodb")]
+    fn dynamodb_stops_at_first_failure() {
+        let data = include_bytes!("../fixtures/example-dynamodb-event.json");
+        let event: dynamodb::Event = serde_json::from_slice(data).unwrap();
+
+        let mut processed = Vec::new();
+        let response = event.process_records(|record| {
+            processed.push(record.event_id.clone());
+            if processed.len() == 2 {
+                Err("failed")
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(2, processed.len());
+        assert_eq!(
+            vec![DynamoDbBatchItemFailure {
+                item_identifier: Some("1405400000000002063282832".to_string()),
+            }],
+            response.batch_item_failures
+        );
+    }
+}
@@ -1,3 +1,6 @@
+mod batch;
+pub use self::batch::BatchEvent;
+
 /// `KinesisEventResponse` is the outer structure to report batch item failures for KinesisEvent.
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]